@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use bevy_polyline::prelude::*;
+
+const FLOW_SPEED: f32 = 0.5;
+
+fn main() {
+    App::new()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_plugin(PolylinePlugin)
+        .add_startup_system(setup)
+        .add_system(flow_system)
+        .run();
+}
+
+/// Marks the materials whose dashes flow along their line.
+#[derive(Component)]
+struct Flowing;
+
+fn setup(
+    mut commands: Commands,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+    mut polylines: ResMut<Assets<Polyline>>,
+) {
+    // A pipe network: flow goes from the first vertex of each line towards its last one
+    let pipes = [
+        vec![Vec3::new(-2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0)],
+        vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(2.0, 1.0, 0.0),
+        ],
+        vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(2.0, -1.0, 0.0),
+        ],
+    ];
+
+    let material = polyline_materials.add(PolylineMaterial {
+        width: 6.0,
+        color: Color::CYAN,
+        join: LineJoin::Round,
+        dash_length: 0.15,
+        gap_length: 0.1,
+        arrowheads: Arrowheads::End,
+        ..Default::default()
+    });
+
+    for vertices in pipes {
+        commands
+            .spawn_bundle(PolylineBundle {
+                polyline: polylines.add(Polyline::from_points(vertices)),
+                material: material.clone(),
+                ..Default::default()
+            })
+            .insert(Flowing);
+    }
+
+    // camera
+    commands.spawn_bundle(PerspectiveCameraBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..PerspectiveCameraBundle::new_3d()
+    });
+}
+
+fn flow_system(
+    time: Res<Time>,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+    query: Query<&Handle<PolylineMaterial>, With<Flowing>>,
+) {
+    // The pipes share their material, only advance it once
+    if let Some(handle) = query.iter().next() {
+        if let Some(material) = polyline_materials.get_mut(handle) {
+            // Wrapping by the dash period keeps the offset small however long the app runs
+            let period = material.dash_length + material.gap_length;
+            material.dash_offset =
+                (material.dash_offset + FLOW_SPEED * time.delta_seconds()).rem_euclid(period);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use bevy::{pbr::PointLightBundle, prelude::*};
+use bevy_polyline::prelude::*;
+
+/// Latitude and longitude of a few airports, in degrees.
+const AIRPORTS: [(f32, f32); 6] = [
+    (49.19, -123.18), // Vancouver
+    (51.47, -0.45),   // London
+    (35.55, 139.78),  // Tokyo
+    (-33.94, 151.18), // Sydney
+    (-23.43, -46.47), // São Paulo
+    (25.25, 55.36),   // Dubai
+];
+
+const ROUTES: [(usize, usize); 6] = [(0, 1), (0, 2), (1, 5), (5, 3), (2, 3), (1, 4)];
+
+fn main() {
+    App::new()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_plugin(PolylinePlugin)
+        .add_startup_system(setup)
+        .add_system(rotator_system)
+        .run();
+}
+
+/// Converts a latitude and longitude in degrees to a direction from the center of the globe,
+/// with the north pole along `Y`.
+fn lat_long(latitude: f32, longitude: f32) -> Vec3 {
+    let (latitude, longitude) = (latitude.to_radians(), longitude.to_radians());
+    Vec3::new(
+        latitude.cos() * longitude.sin(),
+        latitude.sin(),
+        latitude.cos() * longitude.cos(),
+    )
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+    mut polylines: ResMut<Assets<Polyline>>,
+) {
+    // globe
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: 1.0,
+                subdivisions: 5,
+            })),
+            material: standard_materials.add(Color::rgb(0.2, 0.3, 0.5).into()),
+            ..Default::default()
+        })
+        .insert(Rotates)
+        .with_children(|globe| {
+            let route_material = polyline_materials.add(PolylineMaterial {
+                width: 3.0,
+                color: Color::ORANGE,
+                cap: LineCap::Round,
+                ..Default::default()
+            });
+            for (from, to) in ROUTES {
+                let (a, b) = (AIRPORTS[from], AIRPORTS[to]);
+                // Slightly above the surface so the globe doesn't hide the lines
+                let route = Polyline::geodesic(lat_long(a.0, a.1), lat_long(b.0, b.1), 1.01, 64);
+                globe.spawn_bundle(PolylineBundle {
+                    polyline: polylines.add(route),
+                    material: route_material.clone(),
+                    ..Default::default()
+                });
+            }
+        });
+
+    // light
+    commands.spawn_bundle(PointLightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..Default::default()
+    });
+
+    // camera
+    commands.spawn_bundle(PerspectiveCameraBundle {
+        transform: Transform::from_xyz(0.0, 1.0, 3.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..PerspectiveCameraBundle::new_3d()
+    });
+}
+
+/// this component indicates what entities should rotate
+#[derive(Component)]
+struct Rotates;
+
+fn rotator_system(time: Res<Time>, mut query: Query<&mut Transform, With<Rotates>>) {
+    for mut transform in query.iter_mut() {
+        transform.rotate(Quat::from_rotation_y(0.2 * time.delta_seconds()));
+    }
+}
@@ -18,9 +18,9 @@ fn setup(
     mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
     mut polylines: ResMut<Assets<Polyline>>,
 ) {
-    commands.spawn_bundle(PolylineBundle {
-        polyline: polylines.add(Polyline {
-            vertices: vec![
+    commands.spawn_bundle(
+        PolylineBundle::builder()
+            .vertices(vec![
                 Vec3::new(-0.5, -0.5, -0.5),
                 Vec3::new(0.5, -0.5, -0.5),
                 Vec3::new(0.5, 0.5, -0.5),
@@ -29,17 +29,12 @@ fn setup(
                 Vec3::new(0.5, 0.5, 0.5),
                 Vec3::new(0.5, -0.5, 0.5),
                 Vec3::new(-0.5, -0.5, 0.5),
-            ],
-            ..Default::default()
-        }),
-        material: polyline_materials.add(PolylineMaterial {
-            width: 2.0,
-            color: Color::RED,
-            perspective: false,
-            ..Default::default()
-        }),
-        ..Default::default()
-    });
+            ])
+            .width(2.0)
+            .color(Color::RED)
+            .build(&mut polylines, &mut polyline_materials)
+            .unwrap(),
+    );
 
     commands.spawn_bundle(PbrBundle {
         mesh: meshes.add(Mesh::from(shape::Plane { size: 5.0 })),
@@ -22,7 +22,6 @@ fn setup(
         material: polyline_materials.add(PolylineMaterial {
             width: 10.0,
             color: Color::RED,
-            perspective: false,
             ..Default::default()
         }),
         ..Default::default()
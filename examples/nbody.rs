@@ -66,6 +66,7 @@ fn setup(
             .insert_bundle(PolylineBundle {
                 polyline: polylines.add(Polyline {
                     vertices: Vec::with_capacity(TRAIL_LENGTH),
+                    ..Default::default()
                 }),
                 material: polyline_materials.add(PolylineMaterial {
                     width: size,
@@ -75,7 +76,7 @@ fn setup(
                         rng.gen_range(0.4..0.7),
                         0.99,
                     ),
-                    perspective: true,
+                    width_mode: WidthMode::Perspective,
                     ..Default::default()
                 }),
                 ..Default::default()
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use bevy_polyline::prelude::*;
+
+fn main() {
+    App::new()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_plugin(PolylinePlugin)
+        .add_startup_system(setup)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+    mut polylines: ResMut<Assets<Polyline>>,
+) {
+    // axes, drawn below the plot
+    commands.spawn_bundle(Polyline2dBundle {
+        polyline: polylines.add(Polyline {
+            vertices: vec![
+                Vec3::new(-500.0, 0.0, 0.0),
+                Vec3::new(500.0, 0.0, 0.0),
+                Vec3::new(0.0, -300.0, 0.0),
+                Vec3::new(0.0, 300.0, 0.0),
+            ],
+            mode: PolylineMode::LineList,
+            ..Default::default()
+        }),
+        material: polyline_materials.add(PolylineMaterial {
+            width: 2.0,
+            color: Color::GRAY,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    // plot
+    commands.spawn_bundle(Polyline2dBundle {
+        polyline: polylines.add(Polyline::from_points((-500..=500).map(|x| {
+            let x = x as f32;
+            Vec3::new(x, 200.0 * (x / 50.0).sin() * (-x.abs() / 400.0).exp(), 0.0)
+        }))),
+        material: polyline_materials.add(PolylineMaterial {
+            width: 3.0,
+            color: Color::ORANGE,
+            join: LineJoin::Round,
+            antialias: true,
+            ..Default::default()
+        }),
+        transform: Transform::from_xyz(0.0, 0.0, 1.0),
+        ..Default::default()
+    });
+
+    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+}
@@ -0,0 +1,84 @@
+//! Draws the same circle under uniform, non-uniform and animated scales. Lines are expanded to
+//! their width after the entity's transform is applied, so every circle keeps the width of its
+//! material, in pixels for the top row and in world units for the bottom one.
+use bevy::prelude::*;
+use bevy_polyline::prelude::*;
+
+fn main() {
+    App::new()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_plugin(PolylinePlugin)
+        .add_startup_system(setup)
+        .add_system(pulse_system)
+        .run();
+}
+
+/// Marks the entities whose scale is animated.
+#[derive(Component)]
+struct Pulsing;
+
+fn setup(
+    mut commands: Commands,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+    mut polylines: ResMut<Assets<Polyline>>,
+) {
+    // All circles share one polyline, only their transforms differ
+    let circle = polylines.add(Polyline::circle(Vec3::ZERO, Vec3::Z, 0.5, 64));
+    let pixels = polyline_materials.add(PolylineMaterial {
+        width: 8.0,
+        color: Color::ORANGE,
+        join: LineJoin::Round,
+        ..Default::default()
+    });
+    let world_units = polyline_materials.add(PolylineMaterial {
+        width: 0.08,
+        width_mode: WidthMode::WorldUnits,
+        color: Color::CYAN,
+        join: LineJoin::Round,
+        ..Default::default()
+    });
+
+    let scales = [
+        Vec3::ONE,
+        Vec3::splat(2.0),
+        Vec3::new(3.0, 1.0, 1.0),
+        Vec3::new(1.0, 0.25, 1.0),
+    ];
+    for (row, material) in [(1.0, pixels), (-1.0, world_units)] {
+        for (column, scale) in scales.iter().enumerate() {
+            commands.spawn_bundle(PolylineBundle {
+                polyline: circle.clone(),
+                material: material.clone(),
+                transform: Transform {
+                    translation: Vec3::new(column as f32 * 2.5 - 4.5, row * 1.5, 0.0),
+                    scale: *scale,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        }
+        commands
+            .spawn_bundle(PolylineBundle {
+                polyline: circle.clone(),
+                material,
+                transform: Transform::from_xyz(5.5, row * 1.5, 0.0),
+                ..Default::default()
+            })
+            .insert(Pulsing);
+    }
+
+    // camera
+    commands.spawn_bundle(PerspectiveCameraBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..PerspectiveCameraBundle::new_3d()
+    });
+}
+
+fn pulse_system(time: Res<Time>, mut query: Query<&mut Transform, With<Pulsing>>) {
+    let t = time.seconds_since_startup() as f32;
+    for mut transform in query.iter_mut() {
+        // Squash one axis while stretching the other, the width of the line shouldn't follow
+        transform.scale = Vec3::new(1.0 + 0.5 * t.sin(), 1.0 - 0.5 * t.sin(), 1.0);
+    }
+}
@@ -0,0 +1,47 @@
+//! Runs in the browser with WebGL2, e.g. using `wasm-server-runner`:
+//!
+//! ```sh
+//! cargo run --example web --features webgl --target wasm32-unknown-unknown
+//! ```
+use bevy::prelude::*;
+use bevy_polyline::prelude::*;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(PolylinePlugin)
+        .add_startup_system(setup)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+    mut polylines: ResMut<Assets<Polyline>>,
+) {
+    // Caps, joins and arrowheads are drawn from the first and last segments, which WebGL2 needs
+    // the `webgl` feature for
+    commands.spawn_bundle(PolylineBundle {
+        polyline: polylines.add(Polyline::from_points([
+            Vec3::new(-2.0, -1.0, 0.0),
+            Vec3::new(-1.0, 1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(2.0, 1.0, 0.0),
+        ])),
+        material: polyline_materials.add(PolylineMaterial {
+            width: 10.0,
+            color: Color::RED,
+            cap: LineCap::Round,
+            join: LineJoin::Round,
+            arrowheads: Arrowheads::End,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    // camera
+    commands.spawn_bundle(PerspectiveCameraBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..PerspectiveCameraBundle::new_3d()
+    });
+}
@@ -0,0 +1,84 @@
+#[cfg(feature = "render")]
+pub use bevy::render::color::Color;
+#[cfg(not(feature = "render"))]
+pub use linear::Color;
+
+/// Stand-in for Bevy's `Color`, which is part of its renderer, so that per-vertex colors can be
+/// set on polylines built without the `render` feature. It is replaced by Bevy's own `Color`
+/// when rendering, with the same constructors.
+#[cfg(not(feature = "render"))]
+mod linear {
+    use bevy::reflect::{FromReflect, Reflect};
+
+    /// A color, stored in linear RGBA.
+    #[derive(Debug, Clone, Copy, PartialEq, Reflect, FromReflect)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Color {
+        red: f32,
+        green: f32,
+        blue: f32,
+        alpha: f32,
+    }
+
+    impl Color {
+        pub const BLACK: Color = Color::rgb_linear(0.0, 0.0, 0.0);
+        pub const BLUE: Color = Color::rgb_linear(0.0, 0.0, 1.0);
+        pub const GREEN: Color = Color::rgb_linear(0.0, 1.0, 0.0);
+        pub const NONE: Color = Color::rgba_linear(0.0, 0.0, 0.0, 0.0);
+        pub const RED: Color = Color::rgb_linear(1.0, 0.0, 0.0);
+        pub const WHITE: Color = Color::rgb_linear(1.0, 1.0, 1.0);
+
+        /// Creates an opaque color from sRGB components.
+        pub fn rgb(r: f32, g: f32, b: f32) -> Color {
+            Color::rgba(r, g, b, 1.0)
+        }
+
+        /// Creates a color from sRGB components and a linear alpha.
+        pub fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
+            Color::rgba_linear(to_linear(r), to_linear(g), to_linear(b), a)
+        }
+
+        /// Creates an opaque color from linear RGB components.
+        pub const fn rgb_linear(r: f32, g: f32, b: f32) -> Color {
+            Color::rgba_linear(r, g, b, 1.0)
+        }
+
+        /// Creates a color from linear RGBA components.
+        pub const fn rgba_linear(r: f32, g: f32, b: f32, a: f32) -> Color {
+            Color {
+                red: r,
+                green: g,
+                blue: b,
+                alpha: a,
+            }
+        }
+
+        pub fn a(&self) -> f32 {
+            self.alpha
+        }
+
+        pub fn set_a(&mut self, a: f32) -> &mut Self {
+            self.alpha = a;
+            self
+        }
+
+        /// Linear RGBA components, as uploaded to the GPU.
+        pub fn as_linear_rgba_f32(self) -> [f32; 4] {
+            [self.red, self.green, self.blue, self.alpha]
+        }
+    }
+
+    impl Default for Color {
+        fn default() -> Self {
+            Color::WHITE
+        }
+    }
+
+    fn to_linear(srgb: f32) -> f32 {
+        if srgb <= 0.04045 {
+            srgb / 12.92
+        } else {
+            ((srgb + 0.055) / 1.055).powf(2.4)
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+
+use crate::polyline::Polyline;
+
+/// Evaluates the cubic Bézier curve defined by `p0..p3` at `t` in `0.0..=1.0`.
+pub(crate) fn cubic_bezier(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let u = 1.0 - t;
+    p0 * (u * u * u) + p1 * (3.0 * u * u * t) + p2 * (3.0 * u * t * t) + p3 * (t * t * t)
+}
+
+/// Evaluates the cubic Hermite curve from `p0` to `p1` with tangents `m0` and `m1` at `t`.
+fn cubic_hermite(p0: Vec3, m0: Vec3, p1: Vec3, m1: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    p0 * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + m0 * (t3 - 2.0 * t2 + t)
+        + p1 * (3.0 * t2 - 2.0 * t3)
+        + m1 * (t3 - t2)
+}
+
+impl Polyline {
+    /// Creates a line strip approximating the cubic Bézier curve from `p0` to `p3` with control
+    /// points `p1` and `p2`, sampled at `segments + 1` evenly spaced parameter values.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let curve = Polyline::from_cubic_bezier(Vec3::ZERO, Vec3::Y, Vec3::X + Vec3::Y, Vec3::X, 16);
+    /// assert_eq!(curve.vertices.len(), 17);
+    /// assert_eq!(curve.vertices[16], Vec3::X);
+    /// ```
+    pub fn from_cubic_bezier(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, segments: usize) -> Self {
+        Self::from_cubic_bezier_path(p0, [[p1, p2, p3]], segments)
+    }
+
+    /// Creates a line strip approximating a path of chained cubic Bézier curves starting at
+    /// `start`. Each curve is given as `[control1, control2, end]` and starts where the previous
+    /// one ended. Every curve is sampled with `segments_per_curve` segments.
+    pub fn from_cubic_bezier_path(
+        start: Vec3,
+        curves: impl IntoIterator<Item = [Vec3; 3]>,
+        segments_per_curve: usize,
+    ) -> Self {
+        let segments = segments_per_curve.max(1);
+        let mut vertices = vec![start];
+        for [p1, p2, p3] in curves {
+            let p0 = *vertices.last().unwrap();
+            vertices.extend((1..segments).map(|i| {
+                let t = i as f32 / segments as f32;
+                cubic_bezier(p0, p1, p2, p3, t)
+            }));
+            // Push the end point exactly so consecutive curves connect without gaps
+            vertices.push(p3);
+        }
+        Self::from_points(vertices)
+    }
+
+    /// Creates a smooth line strip passing through all of `points`, using a Catmull-Rom spline
+    /// with `segments_per_span` segments between each pair of consecutive points.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let points = [Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0), Vec3::Y];
+    /// let spline = Polyline::from_catmull_rom(&points, 8);
+    /// assert_eq!(spline.vertices.len(), 3 * 8 + 1);
+    /// assert_eq!(spline.vertices[8], Vec3::X);
+    /// ```
+    pub fn from_catmull_rom(points: &[Vec3], segments_per_span: usize) -> Self {
+        Self::from_catmull_rom_with_tension(points, segments_per_span, 0.0)
+    }
+
+    /// Like [`Polyline::from_catmull_rom`], but with an adjustable `tension`: `0.0` is a regular
+    /// Catmull-Rom spline, `1.0` gives straight segments between the points, and negative values
+    /// make the curve overshoot more.
+    ///
+    /// The tangents at both ends are computed by reflecting the second (or second to last) point
+    /// across the end point, so the curve leaves its endpoints heading towards their neighbors.
+    pub fn from_catmull_rom_with_tension(
+        points: &[Vec3],
+        segments_per_span: usize,
+        tension: f32,
+    ) -> Self {
+        if points.len() < 3 {
+            return Self::from_points(points.iter().copied());
+        }
+        let segments = segments_per_span.max(1);
+        let last = points.len() - 1;
+        let point = |i: isize| -> Vec3 {
+            if i < 0 {
+                2.0 * points[0] - points[1]
+            } else if i as usize > last {
+                2.0 * points[last] - points[last - 1]
+            } else {
+                points[i as usize]
+            }
+        };
+        let tangent = |i: isize| (1.0 - tension) * 0.5 * (point(i + 1) - point(i - 1));
+
+        let mut vertices = Vec::with_capacity(last * segments + 1);
+        vertices.push(points[0]);
+        for i in 0..last as isize {
+            let (p0, p1) = (point(i), point(i + 1));
+            let (m0, m1) = (tangent(i), tangent(i + 1));
+            vertices.extend((1..segments).map(|s| {
+                let t = s as f32 / segments as f32;
+                cubic_hermite(p0, m0, p1, m1, t)
+            }));
+            // Push the control points exactly so the curve passes through them
+            vertices.push(p1);
+        }
+        Self::from_points(vertices)
+    }
+
+    /// Creates a line strip following the great circle on the sphere of `radius` around the
+    /// origin, from the direction of `a` to the direction of `b`, for arcs between points on a
+    /// globe. The arc is split into `segments` segments of equal angle, and takes the shorter
+    /// way around the sphere.
+    ///
+    /// `a` and `b` only give directions, so they don't need to lie on the sphere. Antipodal
+    /// points are connected by one of the infinitely many half circles between them, and a
+    /// zero vector is treated as pointing along the other point.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let arc = Polyline::geodesic(Vec3::X, Vec3::Z, 2.0, 8);
+    /// assert_eq!(arc.vertices.len(), 9);
+    /// assert!(arc.vertices.iter().all(|v| (v.length() - 2.0).abs() < 1e-5));
+    /// assert!(arc.vertices[8].abs_diff_eq(Vec3::Z * 2.0, 1e-5));
+    /// ```
+    pub fn geodesic(a: Vec3, b: Vec3, radius: f32, segments: u32) -> Self {
+        let segments = segments.max(1);
+        let from = a.normalize_or_zero();
+        let to = b.normalize_or_zero();
+        let (from, to) = if from == Vec3::ZERO {
+            let to = if to == Vec3::ZERO { Vec3::Y } else { to };
+            (to, to)
+        } else if to == Vec3::ZERO {
+            (from, from)
+        } else {
+            (from, to)
+        };
+        let cos = from.dot(to).clamp(-1.0, 1.0);
+        let angle = cos.acos();
+        // Direction the arc leaves `from` in, within the plane of the great circle. The plane
+        // through identical and antipodal points is undefined, any direction perpendicular to
+        // them gives a great circle through both.
+        let towards = to - from * cos;
+        let tangent = if towards.length_squared() > 1e-10 {
+            towards.normalize()
+        } else {
+            let reference = if from.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
+            from.cross(reference).normalize()
+        };
+        Self::from_points((0..=segments).map(|i| {
+            let (sin, cos) = (angle * i as f32 / segments as f32).sin_cos();
+            (from * cos + tangent * sin) * radius
+        }))
+    }
+}
@@ -0,0 +1,1017 @@
+use bevy::prelude::*;
+
+use crate::{
+    color::Color,
+    polyline::{Polyline, PolylineMode, SEGMENT_QUAD_VERTICES},
+};
+
+impl Polyline {
+    /// Iterates over the start and end point of every segment, in drawing order. Includes the
+    /// closing segment of a closed polyline.
+    pub fn segments(&self) -> impl Iterator<Item = (Vec3, Vec3)> + '_ {
+        self.segment_indices()
+            .map(|[a, b]| (self.vertices[a], self.vertices[b]))
+    }
+
+    /// Whether the polyline is drawn from its `indices`, see [`Polyline::indices`].
+    pub fn is_indexed(&self) -> bool {
+        self.mode == PolylineMode::LineList && !self.indices.is_empty()
+    }
+
+    /// Returns a copy of an indexed polyline with the vertices, and their per-vertex
+    /// attributes, of every segment listed one after the other, the way it is uploaded to the
+    /// GPU with the `webgl` feature. Polylines without indices are returned unchanged.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// // Two edges sharing the vertex at the origin
+    /// let polyline = Polyline {
+    ///     vertices: vec![Vec3::ZERO, Vec3::X, Vec3::Y],
+    ///     indices: vec![0, 1, 0, 2],
+    ///     mode: PolylineMode::LineList,
+    ///     ..Default::default()
+    /// };
+    /// let unindexed = polyline.unindexed();
+    /// assert_eq!(unindexed.vertices, vec![Vec3::ZERO, Vec3::X, Vec3::ZERO, Vec3::Y]);
+    /// ```
+    pub fn unindexed(&self) -> Polyline {
+        if !self.is_indexed() {
+            return self.clone();
+        }
+        let n = self.vertices.len();
+        let indices: Vec<usize> = self.segment_indices().flatten().collect();
+        Polyline {
+            vertices: attribute(&self.vertices, &indices, n),
+            colors: attribute(&self.colors, &indices, n),
+            widths: attribute(&self.widths, &indices, n),
+            scalars: attribute(&self.scalars, &indices, n),
+            indices: Vec::new(),
+            mode: self.mode,
+            closed: self.closed,
+            gpu_capacity: self.gpu_capacity,
+        }
+    }
+
+    /// Returns a copy of the polyline without consecutive duplicate vertices, which form
+    /// segments of zero length with no direction to expand them in. Imported data often has
+    /// such coincident points. The first vertex of every run of identical vertices is kept,
+    /// along with its per-vertex attributes, and a closed loop also drops the last vertices
+    /// that repeat its first one. It is applied to every polyline before it is uploaded.
+    ///
+    /// A line strip whose vertices are all identical keeps two of them, so that its caps still
+    /// draw a dot. The segments of a [`PolylineMode::LineList`] are independent of each other,
+    /// so line lists are returned unchanged, or unindexed if they are indexed.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let polyline = Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::X, Vec3::Y]);
+    /// let deduplicated = polyline.deduplicated();
+    /// assert_eq!(deduplicated.vertices, vec![Vec3::ZERO, Vec3::X, Vec3::Y]);
+    /// assert_eq!(deduplicated.length(), polyline.length());
+    /// ```
+    pub fn deduplicated(&self) -> Polyline {
+        if self.mode == PolylineMode::LineList {
+            return self.unindexed();
+        }
+        let n = self.vertices.len();
+        let mut kept: Vec<usize> = Vec::with_capacity(n);
+        for (i, vertex) in self.vertices.iter().enumerate() {
+            if kept.last().map_or(true, |&k| self.vertices[k] != *vertex) {
+                kept.push(i);
+            }
+        }
+        if self.closed {
+            while kept.len() > 1 && self.vertices[kept[kept.len() - 1]] == self.vertices[kept[0]] {
+                kept.pop();
+            }
+        }
+        if kept.len() == 1 && n > 1 {
+            kept.push(n - 1);
+        }
+        if kept.len() == n {
+            return self.clone();
+        }
+        Polyline {
+            vertices: attribute(&self.vertices, &kept, n),
+            colors: attribute(&self.colors, &kept, n),
+            widths: attribute(&self.widths, &kept, n),
+            scalars: attribute(&self.scalars, &kept, n),
+            indices: Vec::new(),
+            mode: self.mode,
+            closed: self.closed,
+            gpu_capacity: self.gpu_capacity,
+        }
+    }
+
+    /// Returns a copy of the polyline without its vertices with a `NaN` or infinite coordinate,
+    /// which would otherwise break the segments they belong to, or the whole draw. A line strip
+    /// connects the vertices around a dropped one, while a [`PolylineMode::LineList`] drops the
+    /// whole segment. Indexed polylines are unindexed.
+    ///
+    /// Polylines are only cleaned up this way before they are uploaded with
+    /// [`PolylinePluginBuilder::with_vertex_sanitization`].
+    ///
+    /// [`PolylinePluginBuilder::with_vertex_sanitization`]: crate::PolylinePluginBuilder::with_vertex_sanitization
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let polyline = Polyline::from_points([Vec3::ZERO, Vec3::NAN, Vec3::X]);
+    /// assert_eq!(polyline.without_non_finite_vertices().vertices, vec![Vec3::ZERO, Vec3::X]);
+    /// ```
+    pub fn without_non_finite_vertices(&self) -> Polyline {
+        if self.is_indexed() {
+            return self.unindexed().without_non_finite_vertices();
+        }
+        let finite: Vec<bool> = self.vertices.iter().map(|v| v.is_finite()).collect();
+        let keep = match self.mode {
+            PolylineMode::LineStrip => finite,
+            PolylineMode::LineList => {
+                let mut keep = vec![false; finite.len()];
+                for pair in (0..finite.len() / 2).map(|k| 2 * k) {
+                    let segment = finite[pair] && finite[pair + 1];
+                    keep[pair] = segment;
+                    keep[pair + 1] = segment;
+                }
+                keep
+            }
+        };
+        self.kept(&keep)
+    }
+
+    /// Total length of all segments, in local units.
+    pub fn length(&self) -> f32 {
+        self.segments().map(|(a, b)| a.distance(b)).sum()
+    }
+
+    /// Returns the point at distance `s` along the polyline, measured from its first vertex.
+    ///
+    /// `s` is clamped to the length of the polyline, so negative values return the first vertex
+    /// and values past the end return the last one. Returns `None` if there are no vertices. The
+    /// gaps between the segments of a [`PolylineMode::LineList`] don't count towards `s`.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let polyline = Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::new(1.0, 2.0, 0.0)]);
+    /// assert_eq!(polyline.length(), 3.0);
+    /// assert_eq!(polyline.point_at_arc_length(2.0), Some(Vec3::new(1.0, 1.0, 0.0)));
+    /// assert_eq!(polyline.point_at_arc_length(10.0), Some(Vec3::new(1.0, 2.0, 0.0)));
+    /// ```
+    pub fn point_at_arc_length(&self, s: f32) -> Option<Vec3> {
+        let first = *self.vertices.first()?;
+        let mut remaining = s.max(0.0);
+        let mut last = first;
+        for (a, b) in self.segments() {
+            let length = a.distance(b);
+            if remaining <= length {
+                return Some(if length > 0.0 {
+                    a.lerp(b, remaining / length)
+                } else {
+                    a
+                });
+            }
+            remaining -= length;
+            last = b;
+        }
+        Some(last)
+    }
+
+    /// Inserts a vertex on the polyline at distance `s` along it, measured like
+    /// [`Polyline::point_at_arc_length`], and returns its index, for adding control points to a
+    /// line in an editor. Per-vertex colors, widths and scalars are interpolated at the new
+    /// vertex, so they stay aligned with `vertices`.
+    ///
+    /// If `s` falls exactly on an existing vertex, or past either end of the polyline, nothing
+    /// is inserted and the index of that vertex is returned. Returns `None` if the polyline has
+    /// no segment. A segment of a [`PolylineMode::LineList`] is split into two by inserting the
+    /// vertex twice, the returned index being the first copy. Indexed polylines are unindexed
+    /// first, see [`Polyline::unindexed`].
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let mut polyline = Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::new(1.0, 2.0, 0.0)]);
+    /// assert_eq!(polyline.insert_at_arc_length(2.0), Some(2));
+    /// assert_eq!(polyline.vertices[2], Vec3::new(1.0, 1.0, 0.0));
+    /// assert_eq!(polyline.insert_at_arc_length(1.0), Some(1));
+    /// assert_eq!(polyline.vertices.len(), 4);
+    /// ```
+    pub fn insert_at_arc_length(&mut self, s: f32) -> Option<usize> {
+        if self.is_indexed() {
+            *self = self.unindexed();
+        }
+        let mut remaining = s.max(0.0);
+        let mut split = None;
+        let mut last = None;
+        for [a, b] in self.segment_indices() {
+            let length = self.vertices[a].distance(self.vertices[b]);
+            if remaining <= length {
+                let t = if length > 0.0 {
+                    remaining / length
+                } else {
+                    0.0
+                };
+                split = Some((a, b, t));
+                break;
+            }
+            remaining -= length;
+            last = Some(b);
+        }
+        let (a, b, t) = match split {
+            Some((a, _, t)) if t <= 0.0 => return Some(a),
+            Some((_, b, t)) if t >= 1.0 => return Some(b),
+            Some(split) => split,
+            None => return last,
+        };
+
+        // Both segments of a line strip share the new vertex, those of a line list need a copy
+        // each. The closing segment of a loop inserts after the last vertex.
+        let copies = match self.mode {
+            PolylineMode::LineStrip => 1,
+            PolylineMode::LineList => 2,
+        };
+        let index = a + 1;
+        let n = self.vertices.len();
+        let vertex = self.vertices[a].lerp(self.vertices[b], t);
+        insert_copies(&mut self.vertices, index, vertex, copies);
+        if self.colors.len() == n {
+            let color_a = Vec4::from(self.colors[a].as_linear_rgba_f32());
+            let color_b = Vec4::from(self.colors[b].as_linear_rgba_f32());
+            let c = color_a.lerp(color_b, t);
+            insert_copies(
+                &mut self.colors,
+                index,
+                Color::rgba_linear(c.x, c.y, c.z, c.w),
+                copies,
+            );
+        }
+        if self.widths.len() == n {
+            let width = self.widths[a] + (self.widths[b] - self.widths[a]) * t;
+            insert_copies(&mut self.widths, index, width, copies);
+        }
+        if self.scalars.len() == n {
+            let scalar = self.scalars[a] + (self.scalars[b] - self.scalars[a]) * t;
+            insert_copies(&mut self.scalars, index, scalar, copies);
+        }
+        Some(index)
+    }
+
+    /// Returns a copy of the polyline with vertices placed every `spacing` units along its
+    /// length, which evens out irregularly sampled data. The first and last vertex are always
+    /// kept, so a `spacing` longer than the polyline only keeps its endpoints. Per-vertex colors,
+    /// widths and scalars are interpolated at the new vertices.
+    ///
+    /// Each segment of a [`PolylineMode::LineList`] is resampled on its own. A `spacing` of
+    /// `0.0` or less returns an unchanged copy.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let polyline = Polyline::from_points([Vec3::ZERO, Vec3::X * 0.1, Vec3::X * 2.0]);
+    /// let resampled = polyline.resampled(0.5);
+    /// assert_eq!(resampled.vertices.len(), 5);
+    /// assert!(resampled.vertices[1].distance(Vec3::X * 0.5) < 1e-6);
+    /// ```
+    ///
+    /// A closed loop isn't given a last vertex on top of its first one, even when its length is a
+    /// multiple of `spacing`:
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let circle = Polyline::circle(Vec3::ZERO, Vec3::Z, 1.0, 64);
+    /// let resampled = circle.resampled(circle.length() / 16.0);
+    /// assert_eq!(resampled.vertices.len(), 16);
+    /// assert!(resampled.vertices[15].distance(resampled.vertices[0]) > 0.1);
+    /// ```
+    pub fn resampled(&self, spacing: f32) -> Polyline {
+        if spacing <= 0.0 {
+            return self.clone();
+        }
+        if self.is_indexed() {
+            return self.unindexed().resampled(spacing);
+        }
+        let n = self.vertices.len();
+        let samples: Vec<(Vec3, Vec4, f32, f32)> = match self.mode {
+            PolylineMode::LineStrip if self.is_closed() => {
+                let run: Vec<_> = (0..n).chain([0]).collect();
+                let mut samples = self.resample_run(&run, spacing);
+                // The closing segment is drawn from the last vertex to the first one, which
+                // doesn't need to be repeated
+                samples.pop();
+                samples
+            }
+            PolylineMode::LineStrip if n > 0 => {
+                let run: Vec<_> = (0..n).collect();
+                self.resample_run(&run, spacing)
+            }
+            PolylineMode::LineStrip => Vec::new(),
+            // Split each resampled pair back into independent segments
+            PolylineMode::LineList => (0..n / 2)
+                .flat_map(|i| {
+                    let samples = self.resample_run(&[2 * i, 2 * i + 1], spacing);
+                    samples
+                        .windows(2)
+                        .flat_map(|pair| [pair[0], pair[1]])
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+        };
+
+        let use_colors = self.colors.len() == n;
+        let use_widths = self.widths.len() == n;
+        let use_scalars = self.scalars.len() == n;
+        Polyline {
+            vertices: samples.iter().map(|(position, ..)| *position).collect(),
+            colors: if use_colors {
+                samples
+                    .iter()
+                    .map(|(_, c, ..)| Color::rgba_linear(c.x, c.y, c.z, c.w))
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            widths: if use_widths {
+                samples.iter().map(|(_, _, width, _)| *width).collect()
+            } else {
+                Vec::new()
+            },
+            scalars: if use_scalars {
+                samples.iter().map(|(.., scalar)| *scalar).collect()
+            } else {
+                Vec::new()
+            },
+            indices: Vec::new(),
+            mode: self.mode,
+            closed: self.closed,
+            gpu_capacity: self.gpu_capacity,
+        }
+    }
+
+    /// Returns a copy of the polyline with vertices removed using the Ramer-Douglas-Peucker
+    /// algorithm, so that the result deviates at most `epsilon` units from the original. The
+    /// first and last vertex are always kept, along with the colors and widths of the kept
+    /// vertices.
+    ///
+    /// Only line strips are simplified, a [`PolylineMode::LineList`] is returned unchanged.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// // A straight run of collinear points collapses to its endpoints
+    /// let straight = Polyline::from_points((0..100).map(|i| Vec3::X * i as f32));
+    /// assert_eq!(straight.simplified(0.01).vertices, vec![Vec3::ZERO, Vec3::X * 99.0]);
+    ///
+    /// // Corners further than `epsilon` from the simplified line are kept
+    /// let corner = Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0)]);
+    /// assert_eq!(corner.simplified(0.01).vertices.len(), 3);
+    /// ```
+    pub fn simplified(&self, epsilon: f32) -> Polyline {
+        let n = self.vertices.len();
+        if self.mode == PolylineMode::LineList || n < 3 {
+            return self.clone();
+        }
+        // A closed polyline is simplified as a path from its first vertex back to itself
+        let closed = self.is_closed();
+        let run: Vec<_> = if closed {
+            (0..n).chain([0]).collect()
+        } else {
+            (0..n).collect()
+        };
+
+        let mut keep = vec![false; run.len()];
+        keep[0] = true;
+        keep[run.len() - 1] = true;
+        // Iterate with an explicit stack, recursion could overflow on very long polylines
+        let mut ranges = vec![(0, run.len() - 1)];
+        while let Some((first, last)) = ranges.pop() {
+            let (a, b) = (self.vertices[run[first]], self.vertices[run[last]]);
+            let farthest = (first + 1..last)
+                .map(|i| (i, distance_to_segment(self.vertices[run[i]], a, b)))
+                .max_by(|(_, d0), (_, d1)| d0.total_cmp(d1));
+            if let Some((i, distance)) = farthest {
+                if distance > epsilon {
+                    keep[i] = true;
+                    ranges.push((first, i));
+                    ranges.push((i, last));
+                }
+            }
+        }
+        if closed {
+            keep.pop();
+        }
+        self.kept(&keep)
+    }
+
+    /// Returns a copy of the polyline without the vertices at which it turns by `max_angle`
+    /// radians or less, so that runs of nearly collinear vertices collapse while corners are
+    /// kept exactly where they are, for outlines of buildings and other man-made shapes. Unlike
+    /// [`Polyline::simplified`] it doesn't bound how far the result strays from the original,
+    /// but the turn at a vertex is measured from the last kept one, so a gentle curve still keeps
+    /// a vertex whenever it has turned by more than `max_angle` since.
+    ///
+    /// The first and last vertex are always kept, along with the colors, widths and scalars of
+    /// the kept vertices, and duplicates of their previous vertex are dropped. Closed loops can
+    /// also drop their last vertex. Only line strips are simplified, a
+    /// [`PolylineMode::LineList`] is returned unchanged.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let outline = Polyline::from_points([
+    ///     Vec3::ZERO,
+    ///     Vec3::new(1.0, 0.0, 0.0),
+    ///     Vec3::new(2.0, 0.01, 0.0),
+    ///     Vec3::new(2.0, 1.0, 0.0),
+    ///     Vec3::new(2.0, 2.0, 0.0),
+    /// ]);
+    /// let simplified = outline.simplified_by_angle(0.1);
+    /// assert_eq!(
+    ///     simplified.vertices,
+    ///     vec![Vec3::ZERO, Vec3::new(2.0, 0.01, 0.0), Vec3::new(2.0, 2.0, 0.0)]
+    /// );
+    /// ```
+    pub fn simplified_by_angle(&self, max_angle: f32) -> Polyline {
+        let n = self.vertices.len();
+        if self.mode == PolylineMode::LineList || n < 3 {
+            return self.clone();
+        }
+        let closed = self.is_closed();
+        let mut keep = vec![false; n];
+        keep[0] = true;
+        keep[n - 1] = !closed;
+        let mut last = 0;
+        for i in 1..if closed { n } else { n - 1 } {
+            let incoming = self.vertices[i] - self.vertices[last];
+            let outgoing = self.vertices[(i + 1) % n] - self.vertices[i];
+            // A vertex repeating its successor leaves the decision to the successor
+            if incoming == Vec3::ZERO
+                || outgoing == Vec3::ZERO
+                || incoming.angle_between(outgoing) <= max_angle
+            {
+                continue;
+            }
+            keep[i] = true;
+            last = i;
+        }
+        self.kept(&keep)
+    }
+
+    /// Copy of the polyline with only the vertices for which `keep` is `true`, along with their
+    /// per-vertex attributes.
+    fn kept(&self, keep: &[bool]) -> Polyline {
+        let n = self.vertices.len();
+        let use_colors = self.colors.len() == n;
+        let use_widths = self.widths.len() == n;
+        let use_scalars = self.scalars.len() == n;
+        let kept = || (0..n).filter(|&i| keep[i]);
+        Polyline {
+            vertices: kept().map(|i| self.vertices[i]).collect(),
+            colors: if use_colors {
+                kept().map(|i| self.colors[i]).collect()
+            } else {
+                Vec::new()
+            },
+            widths: if use_widths {
+                kept().map(|i| self.widths[i]).collect()
+            } else {
+                Vec::new()
+            },
+            scalars: if use_scalars {
+                kept().map(|i| self.scalars[i]).collect()
+            } else {
+                Vec::new()
+            },
+            indices: Vec::new(),
+            mode: self.mode,
+            closed: self.closed,
+            gpu_capacity: self.gpu_capacity,
+        }
+    }
+
+    /// Returns a copy of the polyline running the other way, see [`Polyline::reverse`].
+    pub fn reversed(&self) -> Polyline {
+        let mut reversed = self.clone();
+        reversed.reverse();
+        reversed
+    }
+
+    /// Flips the direction of the polyline, which swaps its start and end caps and arrowheads,
+    /// and makes arc lengths, and with them dashes and textures, run the other way. Per-vertex
+    /// colors, widths and scalars stay with their vertices.
+    ///
+    /// A closed loop keeps its first vertex, so that arc lengths still start from the same
+    /// point, and the order of the others is reversed. Every segment of a
+    /// [`PolylineMode::LineList`] is flipped, while a trailing unpaired vertex stays last.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let mut polyline = Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::Y]);
+    /// polyline.closed = true;
+    /// polyline.reverse();
+    /// assert_eq!(polyline.vertices, vec![Vec3::ZERO, Vec3::Y, Vec3::X]);
+    /// ```
+    pub fn reverse(&mut self) {
+        if self.is_indexed() {
+            let n = self.indices.len();
+            self.indices[..n - n % 2].reverse();
+            return;
+        }
+        let n = self.vertices.len();
+        let range = match self.mode {
+            PolylineMode::LineStrip if self.is_closed() => 1..n,
+            PolylineMode::LineStrip => 0..n,
+            PolylineMode::LineList => 0..n - n % 2,
+        };
+        self.vertices[range.clone()].reverse();
+        if self.colors.len() == n {
+            self.colors[range.clone()].reverse();
+        }
+        if self.widths.len() == n {
+            self.widths[range.clone()].reverse();
+        }
+        if self.scalars.len() == n {
+            self.scalars[range].reverse();
+        }
+    }
+
+    /// Returns a copy of the polyline moved sideways by `distance` within the plane with the
+    /// normal `plane_normal`, for drawing parallel lines such as lane markings. Positive
+    /// distances offset to the left when looking down `plane_normal`, i.e. towards
+    /// `plane_normal.cross(direction)`, negative distances to the right.
+    ///
+    /// Vertices of a line strip are moved along the miter of the segments meeting there, so that
+    /// the offset segments stay parallel to the original ones. Miters are limited to four times
+    /// `distance` at sharp turns. Offsetting towards the inside of a turn tighter than `distance`
+    /// makes the result fold over itself, which isn't resolved, but never produces NaNs. Each
+    /// segment of a [`PolylineMode::LineList`] is offset on its own, indexed polylines are
+    /// [`unindexed`](Polyline::unindexed) first.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let corner = Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0)]);
+    /// let offset = corner.offset(0.1, Vec3::Z);
+    /// assert!(offset.vertices[1].distance(Vec3::new(0.9, 0.1, 0.0)) < 1e-6);
+    /// ```
+    pub fn offset(&self, distance: f32, plane_normal: Vec3) -> Polyline {
+        // Shared vertices move in a different direction for each of their segments
+        if self.is_indexed() {
+            return self.unindexed().offset(distance, plane_normal);
+        }
+        let side = |a: Vec3, b: Vec3| plane_normal.cross(b - a).normalize_or_zero();
+        let vertices = &self.vertices;
+        let n = vertices.len();
+        let mut offset = self.clone();
+        match self.mode {
+            PolylineMode::LineList => {
+                for pair in offset.vertices.chunks_exact_mut(2) {
+                    let side = side(pair[0], pair[1]);
+                    pair[0] += side * distance;
+                    pair[1] += side * distance;
+                }
+            }
+            PolylineMode::LineStrip => {
+                let closed = self.is_closed();
+                for (i, vertex) in offset.vertices.iter_mut().enumerate() {
+                    let prev = match i {
+                        0 if closed => Some(n - 1),
+                        0 => None,
+                        i => Some(i - 1),
+                    };
+                    let next = match i + 1 {
+                        next if next < n => Some(next),
+                        _ if closed => Some(0),
+                        _ => None,
+                    };
+                    let side_in = prev.map_or(Vec3::ZERO, |prev| side(vertices[prev], *vertex));
+                    let side_out = next.map_or(Vec3::ZERO, |next| side(*vertex, vertices[next]));
+                    // Ends and degenerate segments only have one side to offset along
+                    let reference = if side_in == Vec3::ZERO {
+                        side_out
+                    } else {
+                        side_in
+                    };
+                    let miter = (side_in + side_out).normalize_or_zero();
+                    let cos = miter.dot(reference);
+                    *vertex += if cos > 0.0 {
+                        miter * distance / cos.max(0.25)
+                    } else {
+                        // The line turns back on itself
+                        reference * distance
+                    };
+                }
+            }
+        }
+        offset
+    }
+
+    /// Returns a copy of the line strip with every corner replaced by an arc of `radius` made of
+    /// `segments` segments, so that arc lengths, and with them dashes and textures, follow the
+    /// rounded shape. Unlike [`LineJoin::Round`] this changes the vertices themselves.
+    ///
+    /// The radius of each corner is reduced where its arc would take up more than half of the
+    /// segments next to it, so that the arcs of adjacent corners never overlap. Nearly straight
+    /// corners and corners turning back on themselves are kept as is. The vertices of an arc
+    /// take the colors, widths and scalars of the corner they replace. A
+    /// [`PolylineMode::LineList`] has no corners and is returned unchanged.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let corner = Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0)]);
+    /// let rounded = corner.rounded_corners(0.2, 4);
+    /// assert_eq!(rounded.vertices.len(), 2 + 5);
+    /// assert!(rounded.vertices[1].distance(Vec3::new(0.8, 0.0, 0.0)) < 1e-6);
+    /// ```
+    ///
+    /// [`LineJoin::Round`]: crate::material::LineJoin::Round
+    pub fn rounded_corners(&self, radius: f32, segments: u32) -> Polyline {
+        let n = self.vertices.len();
+        if self.mode == PolylineMode::LineList || radius <= 0.0 || n < 3 {
+            return self.clone();
+        }
+        let segments = segments.max(1);
+        let closed = self.is_closed();
+        let (use_colors, use_widths, use_scalars) = (
+            self.colors.len() == n,
+            self.widths.len() == n,
+            self.scalars.len() == n,
+        );
+
+        let mut rounded = Polyline {
+            mode: self.mode,
+            closed: self.closed,
+            ..Default::default()
+        };
+        for i in 0..n {
+            let corner = self.vertices[i];
+            let neighbours = match i {
+                _ if closed => Some(((i + n - 1) % n, (i + 1) % n)),
+                0 => None,
+                i if i == n - 1 => None,
+                i => Some((i - 1, i + 1)),
+            };
+            let points = neighbours
+                .and_then(|(prev, next)| {
+                    fillet(
+                        self.vertices[prev],
+                        corner,
+                        self.vertices[next],
+                        radius,
+                        segments,
+                    )
+                })
+                .unwrap_or_else(|| vec![corner]);
+
+            let count = points.len();
+            rounded.vertices.extend(points);
+            if use_colors {
+                rounded.colors.extend(std::iter::repeat(self.colors[i]).take(count));
+            }
+            if use_widths {
+                rounded.widths.extend(std::iter::repeat(self.widths[i]).take(count));
+            }
+            if use_scalars {
+                rounded.scalars.extend(std::iter::repeat(self.scalars[i]).take(count));
+            }
+        }
+        rounded
+    }
+
+    /// Merges many polylines into a single [`PolylineMode::LineList`], so that they share one
+    /// vertex buffer and are drawn together instead of with separate draw calls per entity.
+    /// Polylines are never batched automatically; this helper is for apps batching their lines
+    /// by hand, typically once when they are built.
+    ///
+    /// Each polyline's vertices are baked with its `transform`, and the merged polyline is meant
+    /// to be spawned with an identity transform and the material the originals shared. Line
+    /// strips are split into their segments, which means they lose their joins, and caps are
+    /// drawn at the ends of every segment. Per-vertex colors, widths and scalars are kept;
+    /// polylines without them are merged in white, with a width of `1.0` and a scalar of `0.0`,
+    /// which leaves the material unchanged.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let corner = Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0)]);
+    /// let merged = Polyline::merge(
+    ///     (0..10).map(|i| (&corner, Transform::from_xyz(i as f32 * 2.0, 0.0, 0.0))),
+    /// );
+    /// assert_eq!(merged.vertices.len(), 10 * 2 * 2);
+    /// ```
+    pub fn merge<'a>(polylines: impl IntoIterator<Item = (&'a Polyline, Transform)>) -> Polyline {
+        let mut merged = Polyline {
+            mode: PolylineMode::LineList,
+            ..Default::default()
+        };
+        let (mut use_colors, mut use_widths, mut use_scalars) = (false, false, false);
+        for (polyline, transform) in polylines {
+            let matrix = transform.compute_matrix();
+            let start = merged.vertices.len();
+            merged.vertices.extend(polyline.segments().flat_map(|(a, b)| {
+                [matrix.transform_point3(a), matrix.transform_point3(b)]
+            }));
+
+            if polyline.colors.len() == polyline.vertices.len() {
+                if !use_colors {
+                    merged.colors = vec![Color::WHITE; start];
+                    use_colors = true;
+                }
+                merged
+                    .colors
+                    .extend(polyline.segment_attributes(&polyline.colors));
+            } else if use_colors {
+                merged.colors.resize(merged.vertices.len(), Color::WHITE);
+            }
+
+            if polyline.widths.len() == polyline.vertices.len() {
+                if !use_widths {
+                    merged.widths = vec![1.0; start];
+                    use_widths = true;
+                }
+                merged
+                    .widths
+                    .extend(polyline.segment_attributes(&polyline.widths));
+            } else if use_widths {
+                merged.widths.resize(merged.vertices.len(), 1.0);
+            }
+
+            if polyline.scalars.len() == polyline.vertices.len() {
+                if !use_scalars {
+                    merged.scalars = vec![0.0; start];
+                    use_scalars = true;
+                }
+                merged
+                    .scalars
+                    .extend(polyline.segment_attributes(&polyline.scalars));
+            } else if use_scalars {
+                merged.scalars.resize(merged.vertices.len(), 0.0);
+            }
+        }
+        merged
+    }
+
+    /// Expands every segment into the two triangles the vertex shader draws for it, as a CPU
+    /// reference of its math for testing line generators. Returns six vertices per segment, in
+    /// the order of [`Polyline::segments`].
+    ///
+    /// The vertices are treated as if they were already projected to the screen, with `x` and
+    /// `y` in pixels, and are offset by `width` pixels across each segment, scaled by per-vertex
+    /// widths. `z` is interpolated as is. Caps, joins and arrowheads aren't included.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let polyline = Polyline::from_points([Vec3::ZERO, Vec3::new(10.0, 0.0, 0.0)]);
+    /// let triangles = polyline.expanded(2.0);
+    /// assert_eq!(triangles.len(), 6);
+    /// assert_eq!(triangles[1], Vec3::new(10.0, -1.0, 0.0));
+    /// ```
+    pub fn expanded(&self, width: f32) -> Vec<Vec3> {
+        let widths: Vec<f32> = (0..self.vertices.len()).map(|i| self.width_at(i)).collect();
+        let mut segment_widths = self.segment_attributes(&widths);
+        let mut expanded = Vec::new();
+        for (a, b) in self.segments() {
+            let (width_a, width_b) = (
+                segment_widths.next().unwrap(),
+                segment_widths.next().unwrap(),
+            );
+            let x_basis = (b - a).truncate().normalize_or_zero();
+            let y_basis = x_basis.perp();
+            for [x, y, t] in SEGMENT_QUAD_VERTICES {
+                let line_width = width * (width_a + (width_b - width_a) * t);
+                let offset = line_width * (x * x_basis + y * y_basis);
+                expanded.push(a.lerp(b, t) + offset.extend(0.0));
+            }
+        }
+        expanded
+    }
+
+    /// Iterates over the per-vertex `attributes` at the start and end of every segment, in the
+    /// same order as [`Polyline::segments`]. Requires one attribute per vertex.
+    fn segment_attributes<'a, T: Copy>(
+        &'a self,
+        attributes: &'a [T],
+    ) -> impl Iterator<Item = T> + 'a {
+        self.segment_indices().flatten().map(|i| attributes[i])
+    }
+
+    /// Indices of the start and end vertex of every segment, in the order of
+    /// [`Polyline::segments`]. Pairs of `indices` that are out of bounds are skipped.
+    pub(crate) fn segment_indices(&self) -> impl Iterator<Item = [usize; 2]> + '_ {
+        let n = self.vertices.len();
+        let indexed = self.is_indexed();
+        let (step, closing) = match self.mode {
+            PolylineMode::LineStrip => (1, self.is_closed()),
+            PolylineMode::LineList => (2, false),
+        };
+        let len = if indexed { self.indices.len() } else { n };
+        let index = move |i: usize| {
+            if indexed {
+                self.indices[i] as usize
+            } else {
+                i
+            }
+        };
+        (0..len.saturating_sub(1))
+            .step_by(step)
+            .map(move |i| [index(i), index(i + 1)])
+            .filter(move |&[a, b]| a < n && b < n)
+            .chain(closing.then(|| [n - 1, 0]))
+    }
+
+    /// Finds the point of the polyline closest to the ray starting at `ray_origin` going in
+    /// `ray_direction`, both in the local space of the polyline. Returns that point, its distance
+    /// to the ray and the index of the segment it lies on, in the order of
+    /// [`Polyline::segments`], or `None` if the polyline has no segments.
+    ///
+    /// For mouse picking, compare the distance against a threshold to decide whether the line was
+    /// clicked, and use [`Polyline::closest_point_on_transformed`] to pick in world space.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let polyline = Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0)]);
+    /// let (point, distance, segment) = polyline
+    ///     .closest_point_on(Vec3::new(1.0, 0.5, 5.0), -Vec3::Z)
+    ///     .unwrap();
+    /// assert_eq!((point, distance, segment), (Vec3::new(1.0, 0.5, 0.0), 0.0, 1));
+    /// ```
+    pub fn closest_point_on(
+        &self,
+        ray_origin: Vec3,
+        ray_direction: Vec3,
+    ) -> Option<(Vec3, f32, usize)> {
+        closest_segment_point_to_ray(self.segments(), ray_origin, ray_direction)
+    }
+
+    /// Like [`Polyline::closest_point_on`], but with the ray, the returned point and the distance
+    /// in world space, for a polyline drawn with `transform`.
+    pub fn closest_point_on_transformed(
+        &self,
+        transform: &GlobalTransform,
+        ray_origin: Vec3,
+        ray_direction: Vec3,
+    ) -> Option<(Vec3, f32, usize)> {
+        let matrix = transform.compute_matrix();
+        let segments = self
+            .segments()
+            .map(|(a, b)| (matrix.transform_point3(a), matrix.transform_point3(b)));
+        closest_segment_point_to_ray(segments, ray_origin, ray_direction)
+    }
+
+    /// Samples positions, linear colors, widths and scalars every `spacing` units along the
+    /// chain of vertices with indices `run`, including both of its ends.
+    fn resample_run(&self, run: &[usize], spacing: f32) -> Vec<(Vec3, Vec4, f32, f32)> {
+        let use_colors = self.colors.len() == self.vertices.len();
+        let sample = |i: usize| {
+            let color = if use_colors {
+                Vec4::from(self.colors[i].as_linear_rgba_f32())
+            } else {
+                Vec4::ONE
+            };
+            (self.vertices[i], color, self.width_at(i), self.scalar_at(i))
+        };
+
+        let mut samples = vec![sample(run[0])];
+        // Lengths that are a multiple of the spacing would otherwise place a sample a rounding
+        // error away from the last vertex, leaving a degenerate segment, and a degenerate seam
+        // where a loop closes
+        let end = run
+            .windows(2)
+            .map(|pair| self.vertices[pair[0]].distance(self.vertices[pair[1]]))
+            .sum::<f32>()
+            - spacing * 1e-3;
+        let mut next = spacing;
+        let mut travelled = 0.0;
+        for pair in run.windows(2) {
+            let (a, color_a, width_a, scalar_a) = sample(pair[0]);
+            let (b, color_b, width_b, scalar_b) = sample(pair[1]);
+            let length = a.distance(b);
+            while next < travelled + length && next < end {
+                let t = (next - travelled) / length;
+                let width = width_a + (width_b - width_a) * t;
+                let scalar = scalar_a + (scalar_b - scalar_a) * t;
+                samples.push((a.lerp(b, t), color_a.lerp(color_b, t), width, scalar));
+                next += spacing;
+            }
+            travelled += length;
+        }
+        if let [_, .., last] = run {
+            samples.push(sample(*last));
+        }
+        samples
+    }
+}
+
+/// Points of an arc of `radius` tangent to the segments from `corner` to `prev` and `next`, see
+/// [`Polyline::rounded_corners`]. Returns `None` for corners that can't be rounded.
+fn fillet(prev: Vec3, corner: Vec3, next: Vec3, radius: f32, segments: u32) -> Option<Vec<Vec3>> {
+    let (to_prev, to_next) = (prev - corner, next - corner);
+    let (prev_length, next_length) = (to_prev.length(), to_next.length());
+    if prev_length == 0.0 || next_length == 0.0 {
+        return None;
+    }
+    let (to_prev, to_next) = (to_prev / prev_length, to_next / next_length);
+    let angle = to_prev.dot(to_next).clamp(-1.0, 1.0).acos();
+    if angle < 1e-3 || angle > std::f32::consts::PI - 1e-3 {
+        return None;
+    }
+
+    let half_tan = (0.5 * angle).tan();
+    let tangent = (radius / half_tan).min(0.5 * prev_length.min(next_length));
+    let radius = tangent * half_tan;
+    let center = corner + (to_prev + to_next).normalize() * (radius / (0.5 * angle).sin());
+    let (start, end) = (
+        corner + to_prev * tangent - center,
+        corner + to_next * tangent - center,
+    );
+    // Spherical interpolation between both tangent points keeps the arc at `radius`
+    let sweep = std::f32::consts::PI - angle;
+    Some(
+        (0..=segments)
+            .map(|i| {
+                let t = i as f32 / segments as f32;
+                center + (start * ((1.0 - t) * sweep).sin() + end * (t * sweep).sin()) / sweep.sin()
+            })
+            .collect(),
+    )
+}
+
+/// Distance from `point` to the closest point on the segment from `a` to `b`.
+fn distance_to_segment(point: Vec3, a: Vec3, b: Vec3) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(a);
+    }
+    let t = ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+    point.distance(a + t * ab)
+}
+
+/// Closest point of any of `segments` to a ray, see [`Polyline::closest_point_on`].
+fn closest_segment_point_to_ray(
+    segments: impl Iterator<Item = (Vec3, Vec3)>,
+    ray_origin: Vec3,
+    ray_direction: Vec3,
+) -> Option<(Vec3, f32, usize)> {
+    let direction = ray_direction.normalize_or_zero();
+    segments
+        .enumerate()
+        .map(|(index, (a, b))| {
+            let (on_segment, on_ray) = closest_points_segment_ray(a, b, ray_origin, direction);
+            (on_segment, on_segment.distance(on_ray), index)
+        })
+        .min_by(|(_, d0, _), (_, d1, _)| d0.total_cmp(d1))
+}
+
+/// Closest points between the segment from `a` to `b` and the ray from `origin` along the
+/// normalized `direction`, returned as the point on the segment and the point on the ray.
+fn closest_points_segment_ray(a: Vec3, b: Vec3, origin: Vec3, direction: Vec3) -> (Vec3, Vec3) {
+    let segment = b - a;
+    let offset = a - origin;
+    let segment_length_squared = segment.length_squared();
+    let alignment = segment.dot(direction);
+    let segment_offset = segment.dot(offset);
+    let ray_offset = direction.dot(offset);
+
+    // Closest point on the segment to the ray's origin, used when the ray points away
+    let from_origin = if segment_length_squared > 0.0 {
+        (-segment_offset / segment_length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    // With a normalized direction, the denominator is zero for parallel lines
+    let denominator = segment_length_squared - alignment * alignment;
+    let mut s = if denominator > f32::EPSILON * segment_length_squared {
+        ((alignment * ray_offset - segment_offset) / denominator).clamp(0.0, 1.0)
+    } else {
+        from_origin
+    };
+    let mut t = alignment * s + ray_offset;
+    if t < 0.0 {
+        t = 0.0;
+        s = from_origin;
+    }
+    (a + s * segment, origin + t * direction)
+}
+
+/// Inserts `copies` copies of `value` into `values` at `index`.
+fn insert_copies<T: Copy>(values: &mut Vec<T>, index: usize, value: T, copies: usize) {
+    values.splice(index..index, std::iter::repeat(value).take(copies));
+}
+
+/// Per-vertex `attributes` of the vertices at `indices`, or no attributes if there aren't
+/// exactly `n` of them, the number of vertices.
+fn attribute<T: Copy>(attributes: &[T], indices: &[usize], n: usize) -> Vec<T> {
+    if attributes.len() == n {
+        indices.iter().map(|&i| attributes[i]).collect()
+    } else {
+        Vec::new()
+    }
+}
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+
+use crate::{
+    color::Color,
+    polyline::{Polyline, PolylineMode},
+};
+
+/// Splits closer than this fraction of a segment are merged, so that lines crossing exactly at
+/// a vertex aren't split twice.
+const SPLIT_EPSILON: f32 = 1e-5;
+
+impl Polyline {
+    /// Splits the polyline into open line strips wherever it intersects `other`, for trimming
+    /// lines in 2D editing tools. Both polylines are projected onto the plane with the normal
+    /// `plane_normal` and are expected to be in the same space.
+    ///
+    /// A line crossing `other` at a vertex is split once at that vertex, including when it only
+    /// touches `other` with one of its vertices or `other` ends on it. Where segments of both
+    /// lines overlap, the line is split at both ends of the overlap, so the overlapping part is a
+    /// separate piece. The ends of the polyline itself are never split off as empty pieces. A
+    /// closed loop is opened at its intersections, and returned unchanged if there are none.
+    ///
+    /// Per-vertex colors, widths and scalars are interpolated at the splits. Each segment of a
+    /// [`PolylineMode::LineList`] is a separate piece.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let line = Polyline::from_points([-Vec3::X, Vec3::X]);
+    /// let cutter = Polyline::from_points([-Vec3::Y, Vec3::Y]);
+    /// let pieces = line.split_at_intersections(&cutter, Vec3::Z);
+    /// assert_eq!(pieces.len(), 2);
+    /// assert_eq!(pieces[0].vertices, vec![-Vec3::X, Vec3::ZERO]);
+    /// assert_eq!(pieces[1].vertices, vec![Vec3::ZERO, Vec3::X]);
+    /// ```
+    ///
+    /// Overlapping lines and lines touching at their ends:
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// // The overlap with a collinear cutter becomes a piece of its own
+    /// let line = Polyline::from_points([Vec3::X * -2.0, Vec3::X * 2.0]);
+    /// let cutter = Polyline::from_points([-Vec3::X, Vec3::X]);
+    /// let pieces = line.split_at_intersections(&cutter, Vec3::Z);
+    /// assert_eq!(pieces.len(), 3);
+    /// assert_eq!(pieces[0].vertices, vec![Vec3::X * -2.0, -Vec3::X]);
+    /// assert_eq!(pieces[1].vertices, vec![-Vec3::X, Vec3::X]);
+    /// assert_eq!(pieces[2].vertices, vec![Vec3::X, Vec3::X * 2.0]);
+    ///
+    /// // A cutter ending on the line splits it once
+    /// let line = Polyline::from_points([-Vec3::X, Vec3::X]);
+    /// let cutter = Polyline::from_points([Vec3::ZERO, Vec3::Y]);
+    /// let pieces = line.split_at_intersections(&cutter, Vec3::Z);
+    /// assert_eq!(pieces.len(), 2);
+    /// assert_eq!(pieces[0].vertices, vec![-Vec3::X, Vec3::ZERO]);
+    ///
+    /// // A line starting on the cutter isn't split at all
+    /// let line = Polyline::from_points([Vec3::ZERO, Vec3::X]);
+    /// let cutter = Polyline::from_points([-Vec3::Y, Vec3::Y]);
+    /// let pieces = line.split_at_intersections(&cutter, Vec3::Z);
+    /// assert_eq!(pieces.len(), 1);
+    /// assert_eq!(pieces[0].vertices, line.vertices);
+    /// ```
+    pub fn split_at_intersections(&self, other: &Polyline, plane_normal: Vec3) -> Vec<Polyline> {
+        if self.is_indexed() {
+            return self.unindexed().split_at_intersections(other, plane_normal);
+        }
+        let (u, v) = plane_basis(plane_normal);
+        let project = |point: Vec3| Vec2::new(point.dot(u), point.dot(v));
+        let cutters: Vec<(Vec2, Vec2)> = other
+            .segments()
+            .map(|(a, b)| (project(a), project(b) - project(a)))
+            .collect();
+
+        let mut pieces = Vec::new();
+        let mut piece = Polyline::default();
+        let mut split_at_start = false;
+        for (k, [a, b]) in self.segment_indices().enumerate() {
+            if self.mode == PolylineMode::LineList || k == 0 {
+                if piece.vertices.len() > 1 {
+                    pieces.push(std::mem::take(&mut piece));
+                }
+                piece = Polyline::default();
+                self.push_interpolated(&mut piece, a, b, 0.0);
+            }
+
+            let p = project(self.vertices[a]);
+            let r = project(self.vertices[b]) - p;
+            let mut splits: Vec<f32> = cutters
+                .iter()
+                .flat_map(|&(q, s)| segment_intersections(p, r, q, s))
+                .collect();
+            splits.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            let mut last = 0.0;
+            for t in splits {
+                // Splits at the end of a segment are found again at the start of the next one
+                if t > 1.0 - SPLIT_EPSILON {
+                    break;
+                }
+                if t < SPLIT_EPSILON {
+                    // Splitting at the first vertex of the line would leave an empty piece
+                    if piece.vertices.len() > 1 {
+                        pieces.push(std::mem::take(&mut piece));
+                        self.push_interpolated(&mut piece, a, b, 0.0);
+                    }
+                    split_at_start |= k == 0;
+                    continue;
+                }
+                if t - last < SPLIT_EPSILON {
+                    continue;
+                }
+                self.push_interpolated(&mut piece, a, b, t);
+                pieces.push(std::mem::take(&mut piece));
+                self.push_interpolated(&mut piece, a, b, t);
+                last = t;
+            }
+            self.push_interpolated(&mut piece, a, b, 1.0);
+        }
+        if piece.vertices.len() > 1 {
+            pieces.push(piece);
+        }
+
+        // The loop is only open at its first vertex if it was split there
+        if self.is_closed() && !split_at_start {
+            if pieces.len() < 2 {
+                return vec![self.clone()];
+            }
+            let first = pieces.remove(0);
+            let last = pieces.last_mut().unwrap();
+            last.vertices.extend(first.vertices.into_iter().skip(1));
+            last.colors.extend(first.colors.into_iter().skip(1));
+            last.widths.extend(first.widths.into_iter().skip(1));
+            last.scalars.extend(first.scalars.into_iter().skip(1));
+        }
+        pieces
+    }
+
+    /// Pushes the point at `t` along the segment between the vertices `a` and `b` to `piece`,
+    /// along with its interpolated per-vertex attributes.
+    fn push_interpolated(&self, piece: &mut Polyline, a: usize, b: usize, t: f32) {
+        let n = self.vertices.len();
+        piece
+            .vertices
+            .push(self.vertices[a].lerp(self.vertices[b], t));
+        if self.colors.len() == n {
+            let color_a = Vec4::from(self.colors[a].as_linear_rgba_f32());
+            let color_b = Vec4::from(self.colors[b].as_linear_rgba_f32());
+            let c = color_a.lerp(color_b, t);
+            piece.colors.push(Color::rgba_linear(c.x, c.y, c.z, c.w));
+        }
+        if self.widths.len() == n {
+            piece
+                .widths
+                .push(self.widths[a] + (self.widths[b] - self.widths[a]) * t);
+        }
+        if self.scalars.len() == n {
+            piece
+                .scalars
+                .push(self.scalars[a] + (self.scalars[b] - self.scalars[a]) * t);
+        }
+    }
+}
+
+/// Two orthonormal vectors spanning the plane with the given normal.
+fn plane_basis(normal: Vec3) -> (Vec3, Vec3) {
+    let normal = normal.normalize_or_zero();
+    let reference = if normal.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
+    let u = normal.cross(reference).normalize_or_zero();
+    (u, normal.cross(u))
+}
+
+/// Fractions of the segment from `p` to `p + r` at which it intersects the segment from `q` to
+/// `q + s`. Collinear overlapping segments intersect at both ends of the overlap.
+fn segment_intersections(p: Vec2, r: Vec2, q: Vec2, s: Vec2) -> Vec<f32> {
+    let length_squared = r.length_squared();
+    if length_squared == 0.0 {
+        return Vec::new();
+    }
+    let qp = q - p;
+    let denominator = r.perp_dot(s);
+    if denominator.abs() <= f32::EPSILON * length_squared.sqrt() * s.length() {
+        // Parallel segments only intersect if they are on the same line
+        if qp.perp_dot(r).abs() > SPLIT_EPSILON * length_squared {
+            return Vec::new();
+        }
+        let t0 = qp.dot(r) / length_squared;
+        let t1 = (qp + s).dot(r) / length_squared;
+        let (start, end) = (t0.min(t1).max(0.0), t0.max(t1).min(1.0));
+        return if start <= end {
+            vec![start, end]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let t = qp.perp_dot(s) / denominator;
+    let w = qp.perp_dot(r) / denominator;
+    let range = -SPLIT_EPSILON..=1.0 + SPLIT_EPSILON;
+    if range.contains(&t) && range.contains(&w) {
+        vec![t.clamp(0.0, 1.0)]
+    } else {
+        Vec::new()
+    }
+}
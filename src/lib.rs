@@ -1,30 +1,113 @@
-use bevy::{prelude::*, reflect::TypeUuid};
-use material::PolylineMaterialPlugin;
-use polyline::{PolylineBasePlugin, PolylineRenderPlugin};
+use bevy::prelude::*;
+use polyline::PolylineBasePlugin;
+use precision::PolylineF64Plugin;
 
-pub mod material;
+pub mod color;
+mod curves;
+mod geometry;
+mod intersection;
+pub mod path;
 pub mod polyline;
+pub mod precision;
+#[cfg(feature = "render")]
+mod render;
+mod shapes;
+pub mod trail;
+
+#[cfg(feature = "render")]
+pub use render::{diagnostics, immediate, material, SHADER_HANDLE};
 
 pub mod prelude {
-    pub use crate::material::PolylineMaterial;
-    pub use crate::polyline::{Polyline, PolylineBundle};
-    pub use crate::PolylinePlugin;
+    pub use crate::color::Color;
+    pub use crate::path::{PathBuilder, PathCommand};
+    pub use crate::polyline::{
+        Polyline, PolylineInstances, PolylineMode, PolylineOpacity, PolylineReveal, PolylineStatic,
+        PolylineWidth,
+    };
+    pub use crate::precision::PolylineF64;
+    #[cfg(feature = "render")]
+    pub use crate::render::prelude::*;
+    pub use crate::trail::PolylineTrail;
+    pub use crate::{PolylinePlugin, PolylinePluginBuilder, PolylineSystem};
 }
 
-pub const SHADER_HANDLE: HandleUntyped =
-    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 12823766040132746065);
+/// Labels of the systems updating polylines in [`CoreStage::PostUpdate`].
+///
+/// Changes made to polylines anywhere in [`CoreStage::Update`] or earlier are drawn the same
+/// frame, since rendering reads them after the whole main schedule ran. Systems pushing vertices
+/// to polylines in `PostUpdate` should run `.before(PolylineSystem::SyncInline)`, so that trails
+/// are trimmed and bounds computed with their changes.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum PolylineSystem {
+    /// Mirrors `Polyline` components into their assets. It runs in `PostUpdate` so that the
+    /// assets it modifies send their events in the asset stage that follows, and the handles it
+    /// inserts are in place before the render world extracts polylines at the end of the frame.
+    SyncInline,
+    /// Trims and fades [`PolylineTrail`](trail::PolylineTrail)s, after `SyncInline`.
+    Trails,
+    /// Moves the lines drawn to [`ImmediateLines`](immediate::ImmediateLines) into their
+    /// polyline.
+    ImmediateLines,
+    /// Computes the `Aabb` of polylines for frustum culling, after `SyncInline` and `Trails`.
+    CalculateBounds,
+    /// Bakes the transform of new [`PolylineStatic`](polyline::PolylineStatic) entities into
+    /// their polyline, after `SyncInline` and transform propagation.
+    BakeStatic,
+}
 
+/// Draws polylines, or only keeps their assets and components up to date without the `render`
+/// feature. Apps needing other options than the defaults add the plugin returned by
+/// [`PolylinePlugin::builder`] instead.
 pub struct PolylinePlugin;
 
+impl PolylinePlugin {
+    /// Returns a [`PolylinePluginBuilder`] with the same defaults as `PolylinePlugin`, whose
+    /// options are set by chaining its methods before adding it in place of `PolylinePlugin`.
+    pub fn builder() -> PolylinePluginBuilder {
+        PolylinePluginBuilder::default()
+    }
+}
+
 impl Plugin for PolylinePlugin {
-    fn build(&self, app: &mut bevy::prelude::App) {
-        let mut shaders = app.world.get_resource_mut::<Assets<Shader>>().unwrap();
-        shaders.set_untracked(
-            SHADER_HANDLE,
-            Shader::from_wgsl(include_str!("shaders/polyline.wgsl")),
-        );
+    fn build(&self, app: &mut App) {
+        PolylinePlugin::builder().build(app);
+    }
+}
+
+/// A [`PolylinePlugin`] with options, see [`PolylinePlugin::builder`].
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_polyline::prelude::*;
+/// # #[cfg(feature = "render")]
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugin(PolylinePlugin::builder().with_diagnostics())
+///     .run();
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "render"), allow(dead_code))]
+pub struct PolylinePluginBuilder {
+    default_pipeline: bool,
+    diagnostics: bool,
+    sanitize_vertices: bool,
+}
+
+impl Default for PolylinePluginBuilder {
+    fn default() -> Self {
+        Self {
+            default_pipeline: true,
+            diagnostics: false,
+            sanitize_vertices: false,
+        }
+    }
+}
+
+impl Plugin for PolylinePluginBuilder {
+    fn build(&self, app: &mut App) {
         app.add_plugin(PolylineBasePlugin)
-            .add_plugin(PolylineRenderPlugin)
-            .add_plugin(PolylineMaterialPlugin);
+            .add_plugin(PolylineF64Plugin);
+        #[cfg(feature = "render")]
+        self.build_render(app);
     }
 }
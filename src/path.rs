@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+
+use crate::{curves::cubic_bezier, polyline::Polyline};
+
+/// Most segments a single curve is flattened into, however small the tolerance.
+const MAX_CURVE_SEGMENTS: u32 = 1024;
+
+/// A command of a 2D vector path, in the style of SVG paths and of the path events of
+/// `lyon`, to convert vector graphics into polylines with [`Polyline::from_path`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathCommand {
+    /// Starts a new subpath at a point.
+    MoveTo(Vec2),
+    /// Draws a straight line to a point.
+    LineTo(Vec2),
+    /// Draws a cubic Bézier curve to the last point, with the first two as its control points.
+    CubicTo(Vec2, Vec2, Vec2),
+    /// Closes the current subpath, connecting it back to its first point. Further lines and
+    /// curves start a new subpath from that point.
+    Close,
+}
+
+/// Builds a 2D vector path from [`PathCommand`]s and flattens it into polylines.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_polyline::prelude::*;
+/// let polylines = PathBuilder::new()
+///     .move_to(Vec2::ZERO)
+///     .line_to(Vec2::X)
+///     .cubic_to(Vec2::new(1.5, 0.0), Vec2::new(1.5, 1.0), Vec2::ONE)
+///     .close()
+///     .move_to(Vec2::new(2.0, 0.0))
+///     .line_to(Vec2::new(3.0, 0.0))
+///     .build(0.01);
+/// assert_eq!(polylines.len(), 2);
+/// assert!(polylines[0].closed);
+/// assert_eq!(polylines[1].vertices.len(), 2);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct PathBuilder {
+    commands: Vec<PathCommand>,
+}
+
+impl PathBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(mut self, point: Vec2) -> Self {
+        self.commands.push(PathCommand::MoveTo(point));
+        self
+    }
+
+    pub fn line_to(mut self, point: Vec2) -> Self {
+        self.commands.push(PathCommand::LineTo(point));
+        self
+    }
+
+    pub fn cubic_to(mut self, control1: Vec2, control2: Vec2, point: Vec2) -> Self {
+        self.commands
+            .push(PathCommand::CubicTo(control1, control2, point));
+        self
+    }
+
+    pub fn close(mut self) -> Self {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    /// Flattens the path into polylines, see [`Polyline::from_path`].
+    pub fn build(self, tolerance: f32) -> Vec<Polyline> {
+        Polyline::from_path(self.commands, tolerance)
+    }
+}
+
+impl Polyline {
+    /// Flattens a 2D vector path into one line strip per subpath, in the XY plane. Curves are
+    /// split into as few straight segments as keep them within `tolerance` units of the exact
+    /// curve, so smaller tolerances give smoother and denser lines.
+    ///
+    /// Every [`PathCommand::MoveTo`] starts a new polyline, and [`PathCommand::Close`] makes
+    /// the current one [`Polyline::closed`]. A path starting with a line or curve starts at the
+    /// origin. Subpaths of a single point are skipped.
+    pub fn from_path(commands: impl IntoIterator<Item = PathCommand>, tolerance: f32) -> Vec<Self> {
+        let mut polylines = Vec::new();
+        let mut current = vec![Vec3::ZERO];
+        let mut closed = false;
+        for command in commands {
+            match command {
+                PathCommand::MoveTo(point) => {
+                    finish_subpath(&mut polylines, std::mem::take(&mut current), closed);
+                    current.push(point.extend(0.0));
+                    closed = false;
+                }
+                PathCommand::LineTo(point) => {
+                    start_after_close(&mut polylines, &mut current, &mut closed);
+                    current.push(point.extend(0.0));
+                }
+                PathCommand::CubicTo(p1, p2, p3) => {
+                    let (p1, p2, p3) = (p1.extend(0.0), p2.extend(0.0), p3.extend(0.0));
+                    start_after_close(&mut polylines, &mut current, &mut closed);
+                    let p0 = *current.last().unwrap();
+                    // The chords of a curve split into `n` segments stray from it by at most
+                    // `3 / 4 * max |p[i] - 2 p[i + 1] + p[i + 2]| / n²`
+                    let curvature = (p0 - 2.0 * p1 + p2)
+                        .length()
+                        .max((p1 - 2.0 * p2 + p3).length());
+                    let segments = if tolerance > 0.0 {
+                        ((0.75 * curvature / tolerance).sqrt().ceil() as u32)
+                            .clamp(1, MAX_CURVE_SEGMENTS)
+                    } else {
+                        MAX_CURVE_SEGMENTS
+                    };
+                    current.extend(
+                        (1..segments)
+                            .map(|i| cubic_bezier(p0, p1, p2, p3, i as f32 / segments as f32)),
+                    );
+                    current.push(p3);
+                }
+                PathCommand::Close => {
+                    // The closing segment is implied, a last point repeating the first isn't
+                    if current.len() > 1 && current.first() == current.last() {
+                        current.pop();
+                    }
+                    closed = true;
+                }
+            }
+        }
+        finish_subpath(&mut polylines, current, closed);
+        polylines
+    }
+}
+
+/// Lines and curves after a [`PathCommand::Close`] start a new subpath at the first point of the
+/// closed one.
+fn start_after_close(polylines: &mut Vec<Polyline>, current: &mut Vec<Vec3>, closed: &mut bool) {
+    if *closed {
+        let start = current[0];
+        finish_subpath(polylines, std::mem::replace(current, vec![start]), true);
+        *closed = false;
+    }
+}
+
+fn finish_subpath(polylines: &mut Vec<Polyline>, points: Vec<Vec3>, closed: bool) {
+    if points.len() < 2 {
+        return;
+    }
+    polylines.push(Polyline {
+        vertices: points,
+        closed,
+        ..Default::default()
+    });
+}
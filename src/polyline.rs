@@ -1,453 +1,376 @@
-use crate::{material::PolylineMaterial, SHADER_HANDLE};
-use bevy::{
-    core::cast_slice,
-    ecs::system::{
-        lifetimeless::{Read, SQuery, SRes},
-        SystemParamItem,
-    },
-    pbr::{GlobalLightMeta, LightMeta, ViewClusterBindings, ViewShadowBindings},
-    prelude::*,
-    reflect::TypeUuid,
-    render::{
-        render_asset::{RenderAsset, RenderAssetPlugin, RenderAssets},
-        render_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
-        render_phase::{EntityRenderCommand, RenderCommandResult, TrackedRenderPass},
-        render_resource::*,
-        renderer::RenderDevice,
-        texture::BevyDefault,
-        view::{ViewUniform, ViewUniforms},
-        RenderApp, RenderStage,
-    },
-};
+#[cfg(feature = "render")]
+pub use crate::render::{bounds::*, bundle::*, pipeline::*};
+use crate::{color::Color, trail::update_polyline_trails, PolylineSystem};
+use bevy::{prelude::*, reflect::TypeUuid, transform::TransformSystem};
+
+/// Corners of the two triangles every segment is drawn as, matching `positions` in
+/// `shaders/polyline.wgsl`. `y` is the offset across the segment in units of line width and `z`
+/// interpolates from its start to its end, `x` is unused by segments.
+pub const SEGMENT_QUAD_VERTICES: [[f32; 3]; 6] = [
+    [0.0, -0.5, 0.0],
+    [0.0, -0.5, 1.0],
+    [0.0, 0.5, 1.0],
+    [0.0, -0.5, 0.0],
+    [0.0, 0.5, 1.0],
+    [0.0, 0.5, 0.0],
+];
 
 pub struct PolylineBasePlugin;
 
 impl Plugin for PolylineBasePlugin {
     fn build(&self, app: &mut App) {
         app.add_asset::<Polyline>()
-            .add_plugin(RenderAssetPlugin::<Polyline>::default());
-    }
-}
-
-pub struct PolylineRenderPlugin;
-impl Plugin for PolylineRenderPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_plugin(UniformComponentPlugin::<PolylineUniform>::default());
-        app.sub_app_mut(RenderApp)
-            .init_resource::<PolylinePipeline>()
-            .add_system_to_stage(RenderStage::Extract, extract_polylines)
-            .add_system_to_stage(RenderStage::Queue, queue_polyline_bind_group)
-            .add_system_to_stage(RenderStage::Queue, queue_polyline_view_bind_groups);
+            .register_type::<Polyline>()
+            .register_type::<PolylineMode>()
+            .register_type::<Handle<Polyline>>()
+            .register_type::<Option<u32>>()
+            .register_type::<PolylineWidth>()
+            .register_type::<PolylineOpacity>()
+            .register_type::<PolylineReveal>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                sync_inline_polylines.label(PolylineSystem::SyncInline),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_polyline_trails
+                    .label(PolylineSystem::Trails)
+                    .after(PolylineSystem::SyncInline),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                bake_static_polylines
+                    .label(PolylineSystem::BakeStatic)
+                    .after(PolylineSystem::SyncInline)
+                    .after(TransformSystem::TransformPropagate),
+            );
     }
 }
 
-#[derive(Bundle, Default)]
-pub struct PolylineBundle {
-    pub polyline: Handle<Polyline>,
-    pub material: Handle<PolylineMaterial>,
-    pub transform: Transform,
-    pub global_transform: GlobalTransform,
-    /// User indication of whether an entity is visible
-    pub visibility: Visibility,
-    /// Algorithmically-computed indication of whether an entity is visible and should be extracted for rendering
-    pub computed_visibility: ComputedVisibility,
-}
-
-#[derive(Debug, Default, Component, Clone, TypeUuid)]
+/// The geometry of a polyline.
+///
+/// Polylines are assets, so entities referencing the same [`Handle<Polyline>`] share a single
+/// GPU buffer. A `Polyline` can also be inserted directly as a component, in which case it is
+/// copied into the entity's asset whenever it changes, see [`sync_inline_polylines`]. The asset
+/// is created if the entity has no handle, but an existing handle is written through, changing the
+/// line of every other entity sharing it.
+#[derive(Debug, Default, Component, Clone, PartialEq, TypeUuid, Reflect)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 #[uuid = "c76af88a-8afe-405c-9a64-0a7d845d2546"]
 pub struct Polyline {
     pub vertices: Vec<Vec3>,
+    /// Optional per-vertex colors, interpolated along each segment and multiplied with the
+    /// material color.
+    ///
+    /// Only used when it has exactly as many entries as `vertices`; if the lengths don't match
+    /// (including when it is empty) the colors are ignored and the line is drawn with the flat
+    /// material color. Alpha values below `1.0` are only blended if the material's
+    /// [`BlendMode`](crate::material::BlendMode) blends.
+    pub colors: Vec<Color>,
+    /// Optional per-vertex widths, in multiples of the material width and interpolated along
+    /// each segment, for strokes of varying thickness.
+    ///
+    /// Like `colors`, only used when it has exactly as many entries as `vertices`, otherwise the
+    /// whole line is drawn with the material width.
+    pub widths: Vec<f32>,
+    /// Optional per-vertex scalar values, interpolated along each segment and mapped to a color
+    /// through the material's `colormap`, for visualizing data along the line.
+    ///
+    /// Like `colors`, only used when it has exactly as many entries as `vertices`, otherwise
+    /// every vertex has a scalar of `0.0`.
+    pub scalars: Vec<f32>,
+    /// Optional indices into `vertices` consumed in pairs by a [`PolylineMode::LineList`], so
+    /// that edges of graphs sharing their endpoints don't have to repeat them. Per-vertex colors,
+    /// widths and scalars are indexed along with the vertices. Pairs with an index out of bounds
+    /// are skipped, and line strips ignore indices.
+    ///
+    /// Indexed polylines are uploaded with each vertex once, and drawn by reading the vertices of
+    /// every segment from storage buffers by their indices, see [`GpuPolylineIndices`]. WebGL2
+    /// has no storage buffers, so with the `webgl` feature the vertices of every segment are
+    /// uploaded separately instead, see [`Polyline::unindexed`].
+    pub indices: Vec<u32>,
+    pub mode: PolylineMode,
+    /// Connect the last vertex back to the first one. Only applies to
+    /// [`PolylineMode::LineStrip`] with at least three vertices.
+    pub closed: bool,
+    /// Number of vertices of a polyline written by a compute pass of your own rather than
+    /// uploaded from the CPU, such as particle trails integrated on the GPU.
+    ///
+    /// Its vertex buffer is allocated once with room for `gpu_capacity` vertices plus the
+    /// padding described on [`GpuPolyline`], zeroed, and never written by the crate. All
+    /// `gpu_capacity` vertices are always drawn, vertices of width `0.0`, like the zeroed
+    /// ones, are invisible. `vertices` aren't uploaded but still give the bounding box the
+    /// polyline is culled by, so they can hold the corners of the volume the line stays in;
+    /// without vertices the polyline isn't culled. Their length is also what gradients, tapering
+    /// and reveals are relative to, since the length of what the pass writes isn't known.
+    pub gpu_capacity: Option<u32>,
 }
 
-impl RenderAsset for Polyline {
-    type ExtractedAsset = Polyline;
-
-    type PreparedAsset = GpuPolyline;
-
-    type Param = SRes<RenderDevice>;
-
-    fn extract_asset(&self) -> Self::ExtractedAsset {
-        self.clone()
-    }
-
-    fn prepare_asset(
-        polyline: Self::ExtractedAsset,
-        render_device: &mut bevy::ecs::system::SystemParamItem<Self::Param>,
-    ) -> Result<
-        Self::PreparedAsset,
-        bevy::render::render_asset::PrepareAssetError<Self::ExtractedAsset>,
-    > {
-        let vertex_buffer_data = cast_slice(polyline.vertices.as_slice());
-        let vertex_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
-            usage: BufferUsages::VERTEX,
-            label: Some("Polyline Vertex Buffer"),
-            contents: vertex_buffer_data,
-        });
-
-        Ok(GpuPolyline {
-            vertex_buffer,
-            vertex_count: polyline.vertices.len() as u32,
-        })
-    }
+/// How the vertices of a [`Polyline`] are connected into segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Serialize, Deserialize))]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq))]
+pub enum PolylineMode {
+    /// Every vertex is connected to the next one, drawing `vertices.len() - 1` segments.
+    LineStrip,
+    /// Vertices are consumed in pairs, each pair drawing an independent segment. A trailing
+    /// unpaired vertex is ignored. Pairs can also be picked by [`Polyline::indices`].
+    LineList,
 }
 
-#[derive(ShaderType, Component, Clone)]
-pub struct PolylineUniform {
-    #[align(16)]
-    pub transform: Mat4,
-    //pub inverse_transpose_model: Mat4,
+impl Default for PolylineMode {
+    fn default() -> Self {
+        PolylineMode::LineStrip
+    }
 }
 
-/// The GPU-representation of a [`Polyline`]
-#[derive(Debug, Clone)]
-pub struct GpuPolyline {
-    pub vertex_buffer: Buffer,
-    pub vertex_count: u32,
-}
+impl Polyline {
+    /// Creates a line strip going through `points`.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let sine_wave = Polyline::from_points((0..=100).map(|i| {
+    ///     let x = i as f32 * 0.1;
+    ///     Vec3::new(x, x.sin(), 0.0)
+    /// }));
+    /// assert_eq!(sine_wave.vertices.len(), 101);
+    ///
+    /// // Equivalently, by collecting the points
+    /// let sine_wave: Polyline = (0..=100)
+    ///     .map(|i| i as f32 * 0.1)
+    ///     .map(|x| Vec3::new(x, x.sin(), 0.0))
+    ///     .collect();
+    /// assert_eq!(sine_wave.vertices.len(), 101);
+    /// ```
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        points.into_iter().collect()
+    }
 
-pub fn extract_polylines(
-    mut commands: Commands,
-    mut previous_len: Local<usize>,
-    query: Query<(
-        Entity,
-        &ComputedVisibility,
-        &GlobalTransform,
-        &Handle<Polyline>,
-    )>,
-) {
-    let mut values = Vec::with_capacity(*previous_len);
-    for (entity, computed_visibility, transform, handle) in query.iter() {
-        if !computed_visibility.is_visible {
-            continue;
-        }
-        let transform = transform.compute_matrix();
-        values.push((
-            entity,
-            (
-                handle.clone_weak(),
-                PolylineUniform {
-                    transform,
-                    //inverse_transpose_model: transform.inverse().transpose(),
-                },
-            ),
-        ));
+    /// Appends a vertex to the end of the polyline.
+    ///
+    /// Like any other change to a polyline asset, this has to go through
+    /// [`Assets::get_mut`] for the vertex buffer to be updated on the next frame.
+    pub fn push(&mut self, point: Vec3) {
+        self.vertices.push(point);
     }
-    *previous_len = values.len();
-    commands.insert_or_spawn_batch(values);
-}
 
-#[derive(Clone)]
-pub struct PolylinePipeline {
-    pub view_layout: BindGroupLayout,
-    pub polyline_layout: BindGroupLayout,
-}
+    /// Appends a vertex, then drops the oldest vertices so that at most `max_len` remain. Useful
+    /// for trails that would otherwise grow without bound.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let mut trail = Polyline::default();
+    /// for i in 0..10 {
+    ///     trail.push_capped(Vec3::X * i as f32, 4);
+    /// }
+    /// assert_eq!(trail.vertices, vec![Vec3::X * 6.0, Vec3::X * 7.0, Vec3::X * 8.0, Vec3::X * 9.0]);
+    /// ```
+    pub fn push_capped(&mut self, point: Vec3, max_len: usize) {
+        self.push(point);
+        self.truncate_front(max_len);
+    }
 
-impl FromWorld for PolylinePipeline {
-    fn from_world(world: &mut World) -> Self {
-        let render_device = world.get_resource::<RenderDevice>().unwrap();
-        let view_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            entries: &[
-                // View
-                BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::VERTEX,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: true,
-                        min_binding_size: BufferSize::new(ViewUniform::min_size().into()),
-                    },
-                    count: None,
-                },
-            ],
-            label: Some("polyline_view_layout"),
-        });
+    /// Appends `points`, then drops the oldest vertices so that at most `max_len` remain.
+    pub fn extend_capped(&mut self, points: impl IntoIterator<Item = Vec3>, max_len: usize) {
+        self.extend(points);
+        self.truncate_front(max_len);
+    }
 
-        let polyline_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::VERTEX,
-                ty: BindingType::Buffer {
-                    ty: BufferBindingType::Uniform,
-                    has_dynamic_offset: true,
-                    min_binding_size: BufferSize::new(PolylineUniform::min_size().into()),
-                },
-                count: None,
-            }],
-            label: Some("polyline_layout"),
-        });
-        PolylinePipeline {
-            view_layout,
-            polyline_layout,
+    /// Removes vertices from the start until at most `max_len` remain, keeping per-vertex colors,
+    /// widths and scalars aligned with their vertices.
+    pub(crate) fn truncate_front(&mut self, max_len: usize) {
+        let excess = self.vertices.len().saturating_sub(max_len);
+        if excess == 0 {
+            return;
         }
+        if self.colors.len() == self.vertices.len() {
+            self.colors.drain(..excess);
+        }
+        if self.widths.len() == self.vertices.len() {
+            self.widths.drain(..excess);
+        }
+        if self.scalars.len() == self.vertices.len() {
+            self.scalars.drain(..excess);
+        }
+        self.vertices.drain(..excess);
     }
-}
 
-impl SpecializedRenderPipeline for PolylinePipeline {
-    type Key = PolylinePipelineKey;
-    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        let vertex_attributes = vec![
-            VertexAttribute {
-                format: VertexFormat::Float32x3,
-                offset: 0,
-                shader_location: 0,
-            },
-            VertexAttribute {
-                format: VertexFormat::Float32x3,
-                offset: 12,
-                shader_location: 1,
-            },
-        ];
-        let shader_defs = Vec::new();
-        let (label, blend, depth_write_enabled);
+    /// Whether the polyline is actually drawn as a closed loop, see [`Polyline::closed`].
+    pub fn is_closed(&self) -> bool {
+        self.closed && self.mode == PolylineMode::LineStrip && self.vertices.len() > 2
+    }
 
-        if key.contains(PolylinePipelineKey::TRANSPARENT_MAIN_PASS) {
-            label = "transparent_polyline_pipeline".into();
-            blend = Some(BlendState::ALPHA_BLENDING);
-            // For the transparent pass, fragments that are closer will be alpha blended
-            // but their depth is not written to the depth buffer
-            depth_write_enabled = false;
+    /// Width multiplier of the vertex at index `i`, `1.0` unless per-vertex widths are used.
+    pub(crate) fn width_at(&self, i: usize) -> f32 {
+        if self.widths.len() == self.vertices.len() {
+            self.widths[i]
         } else {
-            label = "opaque_polyline_pipeline".into();
-            blend = Some(BlendState::REPLACE);
-            // For the opaque and alpha mask passes, fragments that are closer will replace
-            // the current fragment value in the output and the depth is written to the
-            // depth buffer
-            depth_write_enabled = true;
+            1.0
         }
+    }
 
-        RenderPipelineDescriptor {
-            vertex: VertexState {
-                shader: SHADER_HANDLE.typed::<Shader>(),
-                entry_point: "vertex".into(),
-                shader_defs: shader_defs.clone(),
-                buffers: vec![VertexBufferLayout {
-                    array_stride: 12,
-                    step_mode: VertexStepMode::Instance,
-                    attributes: vertex_attributes,
-                }],
-            },
-            fragment: Some(FragmentState {
-                shader: SHADER_HANDLE.typed::<Shader>(),
-                shader_defs,
-                entry_point: "fragment".into(),
-                targets: vec![ColorTargetState {
-                    format: TextureFormat::bevy_default(),
-                    blend,
-                    write_mask: ColorWrites::ALL,
-                }],
-            }),
-            layout: None, // This is set in `PolylineMaterialPipeline::specialize()`
-            primitive: PrimitiveState {
-                front_face: FrontFace::Ccw,
-                cull_mode: None,
-                unclipped_depth: false,
-                polygon_mode: PolygonMode::Fill,
-                conservative: false,
-                topology: PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-            },
-            depth_stencil: Some(DepthStencilState {
-                format: TextureFormat::Depth32Float,
-                depth_write_enabled,
-                depth_compare: CompareFunction::Greater,
-                stencil: StencilState {
-                    front: StencilFaceState::IGNORE,
-                    back: StencilFaceState::IGNORE,
-                    read_mask: 0,
-                    write_mask: 0,
-                },
-                bias: DepthBiasState {
-                    constant: 0,
-                    slope_scale: 0.0,
-                    clamp: 0.0,
-                },
-            }),
-            multisample: MultisampleState {
-                count: key.msaa_samples(),
-                mask: !0,
-                alpha_to_coverage_enabled: false, //TODO: Do we need this for blending faded lines?
-            },
-            label: Some(label),
+    /// Scalar of the vertex at index `i`, `0.0` unless per-vertex scalars are used.
+    pub(crate) fn scalar_at(&self, i: usize) -> f32 {
+        if self.scalars.len() == self.vertices.len() {
+            self.scalars[i]
+        } else {
+            0.0
         }
     }
 }
 
-bitflags::bitflags! {
-    #[repr(transparent)]
-    // NOTE: Apparently quadro drivers support up to 64x MSAA.
-    /// MSAA uses the highest 6 bits for the MSAA sample count - 1 to support up to 64x MSAA.
-    pub struct PolylinePipelineKey: u32 {
-        const NONE = 0;
-        const PERSPECTIVE = (1 << 0);
-        const TRANSPARENT_MAIN_PASS = (1 << 1);
-        const MSAA_RESERVED_BITS = PolylinePipelineKey::MSAA_MASK_BITS << PolylinePipelineKey::MSAA_SHIFT_BITS;
+impl Extend<Vec3> for Polyline {
+    fn extend<T: IntoIterator<Item = Vec3>>(&mut self, iter: T) {
+        self.vertices.extend(iter);
     }
 }
 
-impl PolylinePipelineKey {
-    const MSAA_MASK_BITS: u32 = 0b111111;
-    const MSAA_SHIFT_BITS: u32 = 32 - 6;
-
-    pub fn from_msaa_samples(msaa_samples: u32) -> Self {
-        let msaa_bits = ((msaa_samples - 1) & Self::MSAA_MASK_BITS) << Self::MSAA_SHIFT_BITS;
-        PolylinePipelineKey::from_bits(msaa_bits).unwrap()
-    }
-
-    pub fn msaa_samples(&self) -> u32 {
-        ((self.bits >> Self::MSAA_SHIFT_BITS) & Self::MSAA_MASK_BITS) + 1
+impl FromIterator<Vec3> for Polyline {
+    fn from_iter<T: IntoIterator<Item = Vec3>>(iter: T) -> Self {
+        Polyline {
+            vertices: iter.into_iter().collect(),
+            ..Default::default()
+        }
     }
 }
 
-pub struct PolylineBindGroup {
-    pub value: BindGroup,
-}
-
-pub fn queue_polyline_bind_group(
+/// Mirrors `Polyline`s inserted inline as components into the asset referenced by the entity's
+/// [`Handle<Polyline>`], adding both the asset and the handle if the entity has none. Runs as
+/// [`PolylineSystem::SyncInline`], before polylines are extracted for rendering.
+///
+/// The asset is overwritten in place, so if the entity's handle is shared with other entities,
+/// editing the component changes the line they draw as well. Entities meant to be edited on their
+/// own should either have no handle, to get an asset of their own, or a handle nobody else holds.
+pub fn sync_inline_polylines(
     mut commands: Commands,
-    polyline_pipeline: Res<PolylinePipeline>,
-    render_device: Res<RenderDevice>,
-    polyline_uniforms: Res<ComponentUniforms<PolylineUniform>>,
+    mut polylines: ResMut<Assets<Polyline>>,
+    query: Query<(Entity, &Polyline, Option<&Handle<Polyline>>), Changed<Polyline>>,
 ) {
-    if let Some(binding) = polyline_uniforms.uniforms().binding() {
-        commands.insert_resource(PolylineBindGroup {
-            value: render_device.create_bind_group(&BindGroupDescriptor {
-                entries: &[BindGroupEntry {
-                    binding: 0,
-                    resource: binding,
-                }],
-                label: Some("polyline_bind_group"),
-                layout: &polyline_pipeline.polyline_layout,
-            }),
-        });
+    for (entity, polyline, handle) in query.iter() {
+        match handle.and_then(|handle| polylines.get_mut(handle)) {
+            Some(asset) => *asset = polyline.clone(),
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(polylines.add(polyline.clone()));
+            }
+        }
     }
 }
 
-#[derive(Component)]
-pub struct PolylineViewBindGroup {
-    pub value: BindGroup,
-}
+/// Marks a polyline that never moves, whose `GlobalTransform` is baked into a copy of its
+/// vertices once, which is then drawn with an identity transform.
+///
+/// The polyline is baked the first frame both its transform and its asset are available.
+/// Changing the transform afterwards won't move the line, and edits to the polyline aren't
+/// picked up either, until the [`BakedPolyline`] component is removed to bake it again. Culling
+/// still uses the bounds of the original polyline at the current transform. Entities with
+/// [`PolylineInstances`] are drawn as usual, since their instances are placed relative to the
+/// entity.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct PolylineStatic;
 
-#[allow(clippy::too_many_arguments)]
-pub fn queue_polyline_view_bind_groups(
+/// Copy of the polyline of a [`PolylineStatic`] entity with its transform baked in, drawn
+/// instead of the entity's own [`Handle<Polyline>`].
+#[derive(Component, Debug, Clone)]
+pub struct BakedPolyline(pub Handle<Polyline>);
+
+/// Bakes the `GlobalTransform` of new [`PolylineStatic`] entities into their polyline.
+#[allow(clippy::type_complexity)]
+pub fn bake_static_polylines(
     mut commands: Commands,
-    render_device: Res<RenderDevice>,
-    polyline_pipeline: Res<PolylinePipeline>,
-    light_meta: Res<LightMeta>,
-    global_light_meta: Res<GlobalLightMeta>,
-    view_uniforms: Res<ViewUniforms>,
-    views: Query<(Entity, &ViewShadowBindings, &ViewClusterBindings)>,
+    mut polylines: ResMut<Assets<Polyline>>,
+    query: Query<
+        (Entity, &Handle<Polyline>, &GlobalTransform),
+        (
+            With<PolylineStatic>,
+            Without<BakedPolyline>,
+            Without<PolylineInstances>,
+        ),
+    >,
 ) {
-    if let (Some(view_binding), Some(_light_binding), Some(_point_light_binding)) = (
-        view_uniforms.uniforms.binding(),
-        light_meta.view_gpu_lights.binding(),
-        global_light_meta.gpu_point_lights.binding(),
-    ) {
-        for (entity, _view_shadow_bindings, _view_cluster_bindings) in views.iter() {
-            let view_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
-                entries: &[
-                    BindGroupEntry {
-                        binding: 0,
-                        resource: view_binding.clone(),
-                    },
-                    /* Can add these bindings in the future if needed
-                    BindGroupEntry {
-                        binding: 1,
-                        resource: light_binding.clone(),
-                    },
-                    BindGroupEntry {
-                        binding: 2,
-                        resource: BindingResource::TextureView(
-                            &view_shadow_bindings.point_light_depth_texture_view,
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 3,
-                        resource: BindingResource::Sampler(&shadow_pipeline.point_light_sampler),
-                    },
-                    BindGroupEntry {
-                        binding: 4,
-                        resource: BindingResource::TextureView(
-                            &view_shadow_bindings.directional_light_depth_texture_view,
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 5,
-                        resource: BindingResource::Sampler(
-                            &shadow_pipeline.directional_light_sampler,
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 6,
-                        resource: point_light_binding.clone(),
-                    },
-                    BindGroupEntry {
-                        binding: 7,
-                        resource: view_cluster_bindings
-                            .cluster_light_index_lists
-                            .binding()
-                            .unwrap(),
-                    },
-                    BindGroupEntry {
-                        binding: 8,
-                        resource: view_cluster_bindings
-                            .cluster_offsets_and_counts
-                            .binding()
-                            .unwrap(),
-                    },
-                    */
-                ],
-                label: Some("polyline_view_bind_group"),
-                layout: &polyline_pipeline.view_layout,
-            });
-
-            commands.entity(entity).insert(PolylineViewBindGroup {
-                value: view_bind_group,
-            });
+    for (entity, handle, transform) in query.iter() {
+        if let Some(polyline) = polylines.get(handle) {
+            let matrix = transform.compute_matrix();
+            let mut baked = polyline.clone();
+            for vertex in &mut baked.vertices {
+                *vertex = matrix.transform_point3(*vertex);
+            }
+            commands
+                .entity(entity)
+                .insert(BakedPolyline(polylines.add(baked)));
         }
     }
 }
 
-pub struct SetPolylineBindGroup<const I: usize>;
-impl<const I: usize> EntityRenderCommand for SetPolylineBindGroup<I> {
-    type Param = (
-        SRes<PolylineBindGroup>,
-        SQuery<Read<DynamicUniformIndex<PolylineUniform>>>,
-    );
-    #[inline]
-    fn render<'w>(
-        _view: Entity,
-        item: Entity,
-        (polyline_bind_group, polyline_query): SystemParamItem<'w, '_, Self::Param>,
-        pass: &mut TrackedRenderPass<'w>,
-    ) -> RenderCommandResult {
-        let polyline_index = polyline_query.get(item).unwrap();
-        pass.set_bind_group(
-            I,
-            &polyline_bind_group.into_inner().value,
-            &[polyline_index.index()],
-        );
-        RenderCommandResult::Success
+/// Overrides the `width` of the [`PolylineMaterial`](crate::material::PolylineMaterial) of an
+/// entity, so that lines of many widths can share one material instead of needing one each. The
+/// width is in the units of the material's [`WidthMode`](crate::material::WidthMode), and
+/// scales with per-vertex widths the same way. Its default of `10.0` is the default width of a
+/// material.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct PolylineWidth(pub f32);
+
+impl Default for PolylineWidth {
+    fn default() -> Self {
+        PolylineWidth(10.0)
     }
 }
 
-pub struct DrawPolyline;
-impl EntityRenderCommand for DrawPolyline {
-    type Param = (SRes<RenderAssets<Polyline>>, SQuery<Read<Handle<Polyline>>>);
-    #[inline]
-    fn render<'w>(
-        _view: Entity,
-        item: Entity,
-        (polylines, pl_query): SystemParamItem<'w, '_, Self::Param>,
-        pass: &mut TrackedRenderPass<'w>,
-    ) -> RenderCommandResult {
-        let pl_handle = pl_query.get(item).unwrap();
-        if let Some(gpu_polyline) = polylines.into_inner().get(pl_handle) {
-            pass.set_vertex_buffer(0, gpu_polyline.vertex_buffer.slice(..));
-            let num_instances = gpu_polyline.vertex_count.max(1) - 1;
-            pass.draw(0..6, 0..num_instances);
-            RenderCommandResult::Success
-        } else {
-            RenderCommandResult::Failure
-        }
+/// Multiplies the alpha of an entity's polyline by an opacity between `0.0` and `1.0`, on top of
+/// its material color, gradient and per-vertex colors, for fading whole groups of lines in and
+/// out without editing their materials. Entities without one are fully opaque.
+///
+/// Lines with an opacity below `1.0` are alpha blended even if their material is opaque, and
+/// lines with an opacity of `0.0` aren't drawn at all.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct PolylineOpacity(pub f32);
+
+impl Default for PolylineOpacity {
+    fn default() -> Self {
+        PolylineOpacity(1.0)
     }
 }
+
+/// Draws only the first `progress` of an entity's polyline, as a fraction of its length between
+/// `0.0` and `1.0`, so that animating `progress` draws the line progressively, for tutorials and
+/// presentations. Entities without one are drawn entirely, and a default reveal of `0.0` hides
+/// the line until its progress is increased.
+///
+/// The line is cut at its exact arc length in the fragment shader, so it grows smoothly rather
+/// than a segment at a time, with its cut faded over a pixel if the material is antialiased.
+/// Caps, joins, arrowheads and markers appear once the line reaches their vertex. Like
+/// [`PolylineMaterial::gradient`](crate::material::PolylineMaterial::gradient), the fraction is
+/// of the total length of the line, so a [`PolylineMode::LineList`] is revealed across all of its
+/// segments in order.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_polyline::prelude::*;
+/// fn draw_over_two_seconds(time: Res<Time>, mut reveals: Query<&mut PolylineReveal>) {
+///     for mut reveal in reveals.iter_mut() {
+///         reveal.progress = (reveal.progress + 0.5 * time.delta_seconds()).min(1.0);
+///     }
+/// }
+/// ```
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct PolylineReveal {
+    pub progress: f32,
+}
+
+/// Draws the [`Polyline`] of an entity once per transform, each relative to the entity's own
+/// transform, instead of once at the entity's transform.
+///
+/// All instances share the same vertex buffer, pipeline and material, and are drawn by the same
+/// draw calls as a single line, except with the `webgl` feature which draws every copy
+/// separately. Instanced entities are frustum culled by the bounds of all of their copies.
+#[derive(Component, Debug, Default, Clone)]
+pub struct PolylineInstances(pub Vec<Transform>);
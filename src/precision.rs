@@ -0,0 +1,95 @@
+use bevy::{math::DVec3, prelude::*, transform::TransformSystem};
+
+use crate::{polyline::Polyline, PolylineSystem};
+
+pub struct PolylineF64Plugin;
+
+impl Plugin for PolylineF64Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(
+            CoreStage::PostUpdate,
+            sync_f64_polylines
+                .before(PolylineSystem::SyncInline)
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// A polyline with double precision vertices, for data spanning coordinate ranges too large
+/// for `f32` positions, which make lines jitter.
+///
+/// Whenever it changes, the vertices are made relative to the center of their bounds in double
+/// precision and stored as an inline [`Polyline`] component, and the entity's `Transform` is
+/// replaced by a translation to that center. Vertices then keep the precision of `f32` close to
+/// the line, and only its placement, which is the same for all vertices, is rounded. Lines far
+/// from the world origin still move in steps as the camera moves, like any other entity, unless
+/// the app keeps the camera close to the origin by moving the world around it.
+///
+/// Since it owns the transform, the entity shouldn't be moved or parented otherwise.
+///
+/// ```no_run
+/// # use bevy::{math::DVec3, prelude::*};
+/// # use bevy_polyline::prelude::*;
+/// # #[cfg(feature = "render")]
+/// fn setup(mut commands: Commands, mut materials: ResMut<Assets<PolylineMaterial>>) {
+///     let orbit = (0..=64).map(|i| {
+///         let angle = i as f64 / 64.0 * std::f64::consts::TAU;
+///         DVec3::new(1.5e11 * angle.cos(), 0.0, 1.5e11 * angle.sin())
+///     });
+///     commands
+///         .spawn_bundle(PolylineBundle {
+///             material: materials.add(PolylineMaterial::default()),
+///             ..Default::default()
+///         })
+///         .insert(PolylineF64 {
+///             vertices: orbit.collect(),
+///             ..Default::default()
+///         });
+/// }
+/// ```
+#[derive(Component, Debug, Default, Clone)]
+pub struct PolylineF64 {
+    /// World space positions of the vertices.
+    pub vertices: Vec<DVec3>,
+    /// Everything else of the polyline, such as its per-vertex colors and its mode. Its own
+    /// vertices are replaced by the converted ones.
+    pub polyline: Polyline,
+}
+
+/// Converts changed [`PolylineF64`]s into a [`Polyline`] relative to their origin.
+pub fn sync_f64_polylines(
+    mut commands: Commands,
+    mut query: Query<
+        (Entity, &PolylineF64, Option<&mut Polyline>, &mut Transform),
+        Changed<PolylineF64>,
+    >,
+) {
+    for (entity, polyline_f64, polyline, mut transform) in query.iter_mut() {
+        let origin = match polyline_f64.vertices.first() {
+            Some(&first) => {
+                let (min, max) = polyline_f64
+                    .vertices
+                    .iter()
+                    .fold((first, first), |(min, max), v| (min.min(*v), max.max(*v)));
+                (min + max) * 0.5
+            }
+            None => DVec3::ZERO,
+        };
+
+        let converted = Polyline {
+            vertices: polyline_f64
+                .vertices
+                .iter()
+                .map(|vertex| (*vertex - origin).as_vec3())
+                .collect(),
+            ..polyline_f64.polyline.clone()
+        };
+        match polyline {
+            Some(mut polyline) => *polyline = converted,
+            None => {
+                commands.entity(entity).insert(converted);
+            }
+        }
+        *transform = Transform::from_translation(origin.as_vec3());
+    }
+}
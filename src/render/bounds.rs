@@ -0,0 +1,182 @@
+use bevy::{
+    prelude::*,
+    render::{
+        primitives::Aabb,
+        view::{NoFrustumCulling, VisibilitySystems},
+    },
+    utils::HashSet,
+};
+
+use crate::{
+    immediate::ImmediateLinesEntity,
+    material::{Arrowheads, LineAlignment, Markers, PolylineMaterial, WidthMode},
+    polyline::{Polyline, PolylineInstances, PolylineWidth},
+    PolylineSystem,
+};
+
+/// Adds [`calculate_polyline_bounds`], which only runs in the main world.
+pub(crate) struct PolylineBoundsPlugin;
+
+impl Plugin for PolylineBoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(
+            CoreStage::PostUpdate,
+            calculate_polyline_bounds
+                .label(PolylineSystem::CalculateBounds)
+                .label(VisibilitySystems::CalculateBounds)
+                .after(PolylineSystem::SyncInline)
+                .after(PolylineSystem::Trails),
+        );
+    }
+}
+
+/// Keeps the [`Aabb`] of polyline entities up to date so that they are frustum culled.
+///
+/// Lines with a [`WidthMode::Perspective`] width have a width roughly proportional to world
+/// units, so their bounds are padded by half of it. Screen-space lines aren't padded, which can
+/// cull them slightly early when their center leaves the view. Entities whose polyline has no
+/// vertices don't get an `Aabb`. Entities with [`PolylineInstances`] get the bounds of all of
+/// their copies, and none without any copy.
+///
+/// Lines that must never be culled, such as a compass or a reticle, can opt out with Bevy's
+/// [`NoFrustumCulling`] component. Their bounds aren't computed, and any `Aabb` they already
+/// have is ignored by Bevy's visibility check.
+#[allow(clippy::type_complexity)]
+pub fn calculate_polyline_bounds(
+    mut commands: Commands,
+    mut polyline_events: EventReader<AssetEvent<Polyline>>,
+    mut material_events: EventReader<AssetEvent<PolylineMaterial>>,
+    polylines: Res<Assets<Polyline>>,
+    materials: Res<Assets<PolylineMaterial>>,
+    removed_instances: RemovedComponents<PolylineInstances>,
+    query: Query<
+        (
+            Entity,
+            &Handle<Polyline>,
+            &Handle<PolylineMaterial>,
+            Option<&PolylineWidth>,
+            Option<&PolylineInstances>,
+            ChangeTrackers<Handle<Polyline>>,
+            ChangeTrackers<Handle<PolylineMaterial>>,
+            Option<ChangeTrackers<PolylineWidth>>,
+            Option<ChangeTrackers<PolylineInstances>>,
+        ),
+        (Without<ImmediateLinesEntity>, Without<NoFrustumCulling>),
+    >,
+) {
+    let mut modified_polylines = HashSet::default();
+    for event in polyline_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            modified_polylines.insert(handle);
+        }
+    }
+    let mut modified_materials = HashSet::default();
+    for event in material_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            modified_materials.insert(handle);
+        }
+    }
+
+    let removed_instances: HashSet<Entity> = removed_instances.iter().collect();
+
+    for (
+        entity,
+        polyline_handle,
+        material_handle,
+        width,
+        instances,
+        polyline_tracker,
+        material_tracker,
+        width_tracker,
+        instances_tracker,
+    ) in query.iter()
+    {
+        let changed = polyline_tracker.is_changed()
+            || material_tracker.is_changed()
+            || width_tracker.map_or(false, |tracker| tracker.is_changed())
+            || instances_tracker.map_or(false, |tracker| tracker.is_changed())
+            || removed_instances.contains(&entity)
+            || modified_polylines.contains(polyline_handle)
+            || modified_materials.contains(material_handle);
+        if !changed {
+            continue;
+        }
+
+        let padding = match materials.get(material_handle) {
+            // Lines in world units are exactly as wide as their width, perspective lines are
+            // treated the same. The width of other lines in world units depends on the camera.
+            Some(material)
+                if material.width_mode != WidthMode::Pixels
+                    || matches!(material.alignment, LineAlignment::Ribbon { .. }) =>
+            {
+                // Arrowheads and markers stick out further than the rest of the line
+                let arrowhead_extent = match material.arrowheads {
+                    Arrowheads::None => 0.5,
+                    _ => material.arrowhead_size.max(0.5),
+                };
+                let marker_extent = match material.markers {
+                    Markers::None => 0.5,
+                    _ => 0.5 * material.marker_size,
+                };
+                arrowhead_extent.max(marker_extent) * width.map_or(material.width, |width| width.0)
+            }
+            _ => 0.0,
+        };
+        match polylines
+            .get(polyline_handle)
+            .and_then(|polyline| {
+                let max_width = (0..polyline.vertices.len())
+                    .map(|i| polyline.width_at(i))
+                    .fold(1.0, f32::max);
+                match instances {
+                    Some(instances) => {
+                        instances_aabb(&polyline.aabb(0.0)?, &instances.0, padding * max_width)
+                    }
+                    None => polyline.aabb(padding * max_width),
+                }
+            })
+        {
+            Some(aabb) => {
+                commands.entity(entity).insert(aabb);
+            }
+            None => {
+                commands.entity(entity).remove::<Aabb>();
+            }
+        }
+    }
+}
+
+/// Bounding box of the copies of `aabb` placed by `instances`, padded by `padding` on every side
+/// after they are transformed. Returns `None` if there are no instances.
+fn instances_aabb(aabb: &Aabb, instances: &[Transform], padding: f32) -> Option<Aabb> {
+    let (aabb_min, aabb_max) = (aabb.min(), aabb.max());
+    let mut corners = instances.iter().flat_map(|instance| {
+        let matrix = instance.compute_matrix();
+        (0..8).map(move |i| {
+            let corner = BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0);
+            matrix.transform_point3(Vec3::select(corner, aabb_max, aabb_min))
+        })
+    });
+    let first = corners.next()?;
+    let (min, max) = corners.fold((first, first), |(min, max), v| (min.min(v), max.max(v)));
+    Some(Aabb::from_min_max(
+        min - Vec3::splat(padding),
+        max + Vec3::splat(padding),
+    ))
+}
+
+impl Polyline {
+    /// Computes the local space bounding box of the vertices, padded by `padding` on every
+    /// side. Returns `None` if there are no vertices.
+    pub fn aabb(&self, padding: f32) -> Option<Aabb> {
+        let first = *self.vertices.first()?;
+        let (min, max) = self
+            .vertices
+            .iter()
+            .fold((first, first), |(min, max), v| (min.min(*v), max.max(*v)));
+        Some(Aabb::from_min_max(
+            min - Vec3::splat(padding),
+            max + Vec3::splat(padding),
+        ))
+    }
+}
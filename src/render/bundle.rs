@@ -0,0 +1,362 @@
+use bevy::{
+    ecs::{query::QueryItem, system::EntityCommands},
+    prelude::*,
+    render::{camera::ExtractedCamera, render_component::ExtractComponent},
+};
+
+use crate::{
+    color::Color,
+    material::{BlendMode, LineAlignment, LineCap, LineJoin, PolylineMaterial, WidthMode},
+    polyline::{Polyline, PolylineMode},
+};
+
+/// A polyline drawn by 3D cameras.
+///
+/// The bundle is saved in Bevy scenes along with [`PolylineWidth`], [`PolylineOpacity`] and
+/// [`PolylineReveal`]. The handles are saved by id, so the assets they point to have to be loaded
+/// or added again with the same ids, see [`PolylineMaterial`] for inline materials instead.
+///
+/// [`PolylineWidth`]: crate::polyline::PolylineWidth
+/// [`PolylineOpacity`]: crate::polyline::PolylineOpacity
+/// [`PolylineReveal`]: crate::polyline::PolylineReveal
+///
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// # use bevy::{
+/// #     asset::AssetPlugin,
+/// #     prelude::*,
+/// #     reflect::TypeRegistryArc,
+/// #     scene::{serde::SceneDeserializer, DynamicScene},
+/// # };
+/// # use bevy_polyline::{
+/// #     material::PolylineMaterialPlugin, polyline::PolylineBasePlugin, prelude::*,
+/// # };
+/// # use serde::de::DeserializeSeed;
+/// let mut app = App::new();
+/// app.add_plugins(MinimalPlugins)
+///     .add_plugin(AssetPlugin)
+///     .add_plugin(PolylineBasePlugin)
+///     .add_plugin(PolylineMaterialPlugin);
+/// let polyline = app
+///     .world
+///     .get_resource_mut::<Assets<Polyline>>()
+///     .unwrap()
+///     .add(Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::Y]));
+/// let material = PolylineMaterial {
+///     color: Color::ORANGE,
+///     cap: LineCap::Round,
+///     stipple: Some((0xf0f0, 2)),
+///     taper: TaperMode::End,
+///     ..Default::default()
+/// };
+/// let material_handle = app
+///     .world
+///     .get_resource_mut::<Assets<PolylineMaterial>>()
+///     .unwrap()
+///     .add(material.clone());
+/// app.world
+///     .spawn()
+///     .insert_bundle(PolylineBundle {
+///         polyline: polyline.clone(),
+///         material: material_handle,
+///         ..Default::default()
+///     })
+///     .insert(PolylineWidth(4.0))
+///     .insert(PolylineOpacity(0.5))
+///     .insert(PolylineReveal { progress: 0.25 });
+///
+/// let registry = app.world.get_resource::<TypeRegistryArc>().unwrap().clone();
+/// let scene = DynamicScene::from_world(&app.world, &registry);
+/// let ron = scene.serialize_ron(&registry).unwrap();
+///
+/// let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+/// let scene = SceneDeserializer {
+///     type_registry: &registry.read(),
+/// }
+/// .deserialize(&mut deserializer)
+/// .unwrap();
+/// let mut world = World::new();
+/// world.insert_resource(registry);
+/// scene.write_to_world(&mut world, &mut Default::default()).unwrap();
+///
+/// let (loaded_polyline, loaded_material, width, opacity, reveal) = world
+///     .query::<(
+///         &Handle<Polyline>,
+///         &Handle<PolylineMaterial>,
+///         &PolylineWidth,
+///         &PolylineOpacity,
+///         &PolylineReveal,
+///     )>()
+///     .single(&world);
+/// assert_eq!(*loaded_polyline, polyline);
+/// let materials = app.world.get_resource::<Assets<PolylineMaterial>>().unwrap();
+/// assert_eq!(materials.get(loaded_material), Some(&material));
+/// assert_eq!(*width, PolylineWidth(4.0));
+/// assert_eq!(*opacity, PolylineOpacity(0.5));
+/// assert_eq!(*reveal, PolylineReveal { progress: 0.25 });
+/// # }
+/// ```
+#[derive(Bundle, Default)]
+pub struct PolylineBundle {
+    pub polyline: Handle<Polyline>,
+    pub material: Handle<PolylineMaterial>,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    /// User indication of whether an entity is visible
+    pub visibility: Visibility,
+    /// Algorithmically-computed indication of whether an entity is visible and should be extracted for rendering
+    pub computed_visibility: ComputedVisibility,
+}
+
+/// Marks a polyline to be drawn by 2D cameras instead of 3D ones, see [`Polyline2dBundle`].
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct Polyline2d;
+
+impl ExtractComponent for Polyline2d {
+    type Query = &'static Self;
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Draws a polyline only with the camera of this name, such as a HUD or minimap camera listed in
+/// Bevy's `ActiveCameras`, instead of with every camera that sees it.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_polyline::prelude::*;
+/// fn spawn_reticle(mut commands: Commands) {
+///     commands
+///         .spawn_bundle(PolylineBundle::default())
+///         .insert(PolylineCamera::new("hud"));
+/// }
+/// ```
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct PolylineCamera(pub String);
+
+impl PolylineCamera {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    /// Whether a polyline with this target, if any, is drawn by the view of `camera`.
+    pub(crate) fn targets(target: Option<&Self>, camera: Option<&ExtractedCamera>) -> bool {
+        target.map_or(true, |target| {
+            camera.and_then(|camera| camera.name.as_deref()) == Some(target.0.as_str())
+        })
+    }
+}
+
+impl ExtractComponent for PolylineCamera {
+    type Query = &'static Self;
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        item.clone()
+    }
+}
+
+/// A polyline drawn by 2D cameras, in their transparent pass.
+///
+/// There is no depth buffer in 2D, so polylines are layered by the z translation of their
+/// transform, with higher values drawn on top of lower ones like sprites. The material's `width`
+/// is in screen pixels and [`WidthMode::Perspective`] is best avoided with the orthographic 2D
+/// camera.
+#[derive(Bundle, Default)]
+pub struct Polyline2dBundle {
+    pub polyline: Handle<Polyline>,
+    pub material: Handle<PolylineMaterial>,
+    pub polyline_2d: Polyline2d,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    /// User indication of whether an entity is visible
+    pub visibility: Visibility,
+    /// Algorithmically-computed indication of whether an entity is visible and should be extracted for rendering
+    pub computed_visibility: ComputedVisibility,
+}
+
+impl PolylineBundle {
+    /// Starts building a bundle from a [`Polyline`] and [`PolylineMaterial`], see
+    /// [`PolylineBundleBuilder`].
+    pub fn builder() -> PolylineBundleBuilder {
+        PolylineBundleBuilder::default()
+    }
+}
+
+/// Builds a [`PolylineBundle`], adding its polyline and material to their asset collections.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_polyline::prelude::*;
+/// fn setup(
+///     mut commands: Commands,
+///     mut polylines: ResMut<Assets<Polyline>>,
+///     mut materials: ResMut<Assets<PolylineMaterial>>,
+/// ) {
+///     let bundle = PolylineBundle::builder()
+///         .vertices(vec![-Vec3::ONE, Vec3::ONE])
+///         .color(Color::RED)
+///         .width(3.0)
+///         .build(&mut polylines, &mut materials)
+///         .expect("a line needs at least two vertices");
+///     commands.spawn_bundle(bundle);
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct PolylineBundleBuilder {
+    polyline: Polyline,
+    material: PolylineMaterial,
+    transform: Transform,
+}
+
+impl PolylineBundleBuilder {
+    /// Replaces the whole polyline, discarding previously set vertices, colors and mode.
+    pub fn polyline(mut self, polyline: Polyline) -> Self {
+        self.polyline = polyline;
+        self
+    }
+
+    /// Replaces the whole material, discarding previously set material properties.
+    pub fn material(mut self, material: PolylineMaterial) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn vertices(mut self, vertices: impl Into<Vec<Vec3>>) -> Self {
+        self.polyline.vertices = vertices.into();
+        self
+    }
+
+    pub fn colors(mut self, colors: impl Into<Vec<Color>>) -> Self {
+        self.polyline.colors = colors.into();
+        self
+    }
+
+    pub fn widths(mut self, widths: impl Into<Vec<f32>>) -> Self {
+        self.polyline.widths = widths.into();
+        self
+    }
+
+    pub fn scalars(mut self, scalars: impl Into<Vec<f32>>) -> Self {
+        self.polyline.scalars = scalars.into();
+        self
+    }
+
+    pub fn mode(mut self, mode: PolylineMode) -> Self {
+        self.polyline.mode = mode;
+        self
+    }
+
+    pub fn closed(mut self, closed: bool) -> Self {
+        self.polyline.closed = closed;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.material.color = color;
+        self
+    }
+
+    pub fn width(mut self, width: f32) -> Self {
+        self.material.width = width;
+        self
+    }
+
+    pub fn width_mode(mut self, width_mode: WidthMode) -> Self {
+        self.material.width_mode = width_mode;
+        self
+    }
+
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.material.cap = cap;
+        self
+    }
+
+    pub fn join(mut self, join: LineJoin) -> Self {
+        self.material.join = join;
+        self
+    }
+
+    pub fn blend(mut self, blend: BlendMode) -> Self {
+        self.material.blend = blend;
+        self
+    }
+
+    pub fn alignment(mut self, alignment: LineAlignment) -> Self {
+        self.material.alignment = alignment;
+        self
+    }
+
+    pub fn fragment_shader(mut self, fragment_shader: Handle<Shader>) -> Self {
+        self.material.fragment_shader = Some(fragment_shader);
+        self
+    }
+
+    pub fn transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Adds the polyline and material as assets and returns the bundle referencing them.
+    ///
+    /// Returns `None` without adding anything if the polyline has fewer than two vertices.
+    pub fn build(
+        self,
+        polylines: &mut Assets<Polyline>,
+        materials: &mut Assets<PolylineMaterial>,
+    ) -> Option<PolylineBundle> {
+        if self.polyline.vertices.len() < 2 {
+            return None;
+        }
+        Some(PolylineBundle {
+            polyline: polylines.add(self.polyline),
+            material: materials.add(self.material),
+            transform: self.transform,
+            ..Default::default()
+        })
+    }
+}
+
+/// Spawns a polyline in a single call, for drawing a line without setting up its assets.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_polyline::prelude::*;
+/// fn setup(mut commands: Commands, mut materials: ResMut<Assets<PolylineMaterial>>) {
+///     let red = materials.add(PolylineMaterial {
+///         color: Color::RED,
+///         ..Default::default()
+///     });
+///     commands.spawn_polyline([-Vec3::ONE, Vec3::ONE], red);
+/// }
+/// ```
+pub trait SpawnPolylineExt<'w, 's> {
+    /// Spawns a [`PolylineBundle`] drawing a line strip through `points` with `material`, and
+    /// returns the entity to add further components or a `Transform` to it.
+    ///
+    /// The points are inserted as an inline [`Polyline`] component, which is copied into an
+    /// asset of its own at the end of the frame, and again whenever the component is edited.
+    fn spawn_polyline<'a>(
+        &'a mut self,
+        points: impl IntoIterator<Item = Vec3>,
+        material: Handle<PolylineMaterial>,
+    ) -> EntityCommands<'w, 's, 'a>;
+}
+
+impl<'w, 's> SpawnPolylineExt<'w, 's> for Commands<'w, 's> {
+    fn spawn_polyline<'a>(
+        &'a mut self,
+        points: impl IntoIterator<Item = Vec3>,
+        material: Handle<PolylineMaterial>,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut entity = self.spawn_bundle(PolylineBundle {
+            material,
+            ..Default::default()
+        });
+        entity.insert(Polyline::from_points(points));
+        entity
+    }
+}
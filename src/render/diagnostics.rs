@@ -0,0 +1,201 @@
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::*,
+    render::{render_resource::BufferId, RenderApp},
+};
+
+use crate::polyline::Polyline;
+
+/// Statistics of the polylines rendered during the previous frame, for debug overlays.
+///
+/// Updated at the start of every frame from counters kept by the render world, which only cost
+/// a few atomic additions per polyline drawn. Polylines drawn by several cameras, or as several
+/// [`PolylineInstances`](crate::polyline::PolylineInstances), are counted once per camera and
+/// copy.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PolylineStats {
+    /// Number of polylines drawn.
+    pub polylines: usize,
+    /// Number of segments of the polylines drawn.
+    pub segments: usize,
+    /// Number of draw calls issued for segments, caps, joins and arrowheads.
+    pub draw_calls: usize,
+    /// Number of vertices of all polylines on the GPU, drawn or not.
+    pub vertices: usize,
+    /// Size of the vertex buffers of all polylines on the GPU, in bytes.
+    pub buffer_bytes: u64,
+    /// Bytes of vertex data written to the GPU.
+    pub uploaded_bytes: u64,
+    /// Number of pipelines specialized, once for every new combination of material settings,
+    /// polyline mode and MSAA sample count drawn. Frames only drawing combinations drawn before
+    /// reuse their cached pipelines and count `0`.
+    pub pipelines_specialized: usize,
+}
+
+/// Sent whenever the vertex buffer of a polyline is created or written to, for tools tracking
+/// how often and how much polyline data is uploaded.
+///
+/// Buffers are uploaded by the render world, after the main world is done with the frame, so
+/// the events of a frame are sent at the start of the next one. Polylines are assets, so an
+/// event names the polyline rather than the entities drawing it.
+#[derive(Debug, Clone)]
+pub struct PolylineBufferUpdated {
+    /// Weak handle of the polyline uploaded.
+    pub polyline: Handle<Polyline>,
+    /// Id of its vertex buffer.
+    pub buffer_id: BufferId,
+    /// Number of bytes written to the buffer, which is only the part that changed for updates,
+    /// and `0` for buffers allocated for [`Polyline::gpu_capacity`].
+    pub byte_len: u64,
+    /// Whether the buffer was newly allocated, replacing the previous buffer of the polyline if
+    /// it had one, rather than written to in place.
+    pub created: bool,
+}
+
+/// Keeps the [`PolylineStats`] resource up to date and sends [`PolylineBufferUpdated`] events.
+pub(crate) struct PolylineStatsPlugin;
+
+impl Plugin for PolylineStatsPlugin {
+    fn build(&self, app: &mut App) {
+        let render_stats = RenderPolylineStats::default();
+        app.insert_resource(render_stats.clone())
+            .init_resource::<PolylineStats>()
+            .add_event::<PolylineBufferUpdated>()
+            .add_system_to_stage(CoreStage::First, update_polyline_stats);
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.insert_resource(render_stats);
+        }
+    }
+}
+
+/// Counters behind [`PolylineStats`], updated by the render world and shared with the main
+/// world which resets them once read.
+#[derive(Clone, Default)]
+pub struct RenderPolylineStats(Arc<RenderPolylineCounters>);
+
+#[derive(Default)]
+struct RenderPolylineCounters {
+    polylines: AtomicUsize,
+    segments: AtomicUsize,
+    draw_calls: AtomicUsize,
+    vertices: AtomicUsize,
+    buffer_bytes: AtomicU64,
+    uploaded_bytes: AtomicU64,
+    pipelines_specialized: AtomicUsize,
+    buffer_updates: Mutex<Vec<PolylineBufferUpdated>>,
+}
+
+impl RenderPolylineStats {
+    pub(crate) fn add_drawn(&self, copies: u32, segments: u32) {
+        self.0
+            .polylines
+            .fetch_add(copies as usize, Ordering::Relaxed);
+        self.0
+            .segments
+            .fetch_add((copies * segments) as usize, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_draw_call(&self) {
+        self.0.draw_calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_uploaded(&self, bytes: u64) {
+        self.0.uploaded_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_pipeline_specialized(&self) {
+        self.0.pipelines_specialized.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_buffer_update(&self, update: PolylineBufferUpdated) {
+        self.0.buffer_updates.lock().unwrap().push(update);
+    }
+
+    pub(crate) fn set_buffers(&self, vertices: usize, bytes: u64) {
+        self.0.vertices.store(vertices, Ordering::Relaxed);
+        self.0.buffer_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    fn take(&self) -> PolylineStats {
+        let counters = &self.0;
+        PolylineStats {
+            polylines: counters.polylines.swap(0, Ordering::Relaxed),
+            segments: counters.segments.swap(0, Ordering::Relaxed),
+            draw_calls: counters.draw_calls.swap(0, Ordering::Relaxed),
+            vertices: counters.vertices.load(Ordering::Relaxed),
+            buffer_bytes: counters.buffer_bytes.load(Ordering::Relaxed),
+            uploaded_bytes: counters.uploaded_bytes.swap(0, Ordering::Relaxed),
+            pipelines_specialized: counters.pipelines_specialized.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+fn update_polyline_stats(
+    mut stats: ResMut<PolylineStats>,
+    mut buffer_updates: EventWriter<PolylineBufferUpdated>,
+    render_stats: Res<RenderPolylineStats>,
+) {
+    *stats = render_stats.take();
+    let updates = std::mem::take(&mut *render_stats.0.buffer_updates.lock().unwrap());
+    for update in updates {
+        buffer_updates.send(update);
+    }
+}
+
+/// Reports the [`PolylineStats`] to Bevy's [`Diagnostics`], enabled with
+/// [`PolylinePluginBuilder::with_diagnostics`](crate::PolylinePluginBuilder::with_diagnostics).
+///
+/// Like Bevy's own diagnostics plugins it needs the `DiagnosticsPlugin` from `DefaultPlugins`,
+/// and its measurements can be printed with the `LogDiagnosticsPlugin`.
+pub struct PolylineDiagnosticsPlugin;
+
+impl PolylineDiagnosticsPlugin {
+    /// Number of polylines drawn during the previous frame, see [`PolylineStats::polylines`].
+    pub const POLYLINES: DiagnosticId =
+        DiagnosticId::from_u128(298085667256651877638217914013298371646);
+    /// Number of segments drawn during the previous frame, see [`PolylineStats::segments`].
+    pub const SEGMENTS: DiagnosticId =
+        DiagnosticId::from_u128(35384652563554749104000167578627883588);
+    /// Bytes of vertex data written to the GPU during the previous frame.
+    pub const UPLOADED_BYTES: DiagnosticId =
+        DiagnosticId::from_u128(185836200523916217831986532623151798588);
+}
+
+impl Plugin for PolylineDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_polyline_diagnostics)
+            .add_system(measure_polyline_diagnostics);
+    }
+}
+
+fn setup_polyline_diagnostics(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics.add(Diagnostic::new(
+        PolylineDiagnosticsPlugin::POLYLINES,
+        "polylines",
+        20,
+    ));
+    diagnostics.add(Diagnostic::new(
+        PolylineDiagnosticsPlugin::SEGMENTS,
+        "polyline_segments",
+        20,
+    ));
+    diagnostics.add(Diagnostic::new(
+        PolylineDiagnosticsPlugin::UPLOADED_BYTES,
+        "polyline_uploaded_bytes",
+        20,
+    ));
+}
+
+fn measure_polyline_diagnostics(mut diagnostics: ResMut<Diagnostics>, stats: Res<PolylineStats>) {
+    diagnostics.add_measurement(PolylineDiagnosticsPlugin::POLYLINES, stats.polylines as f64);
+    diagnostics.add_measurement(PolylineDiagnosticsPlugin::SEGMENTS, stats.segments as f64);
+    diagnostics.add_measurement(
+        PolylineDiagnosticsPlugin::UPLOADED_BYTES,
+        stats.uploaded_bytes as f64,
+    );
+}
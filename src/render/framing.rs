@@ -0,0 +1,88 @@
+use bevy::{
+    prelude::*,
+    render::camera::{OrthographicProjection, PerspectiveProjection},
+};
+
+use crate::polyline::Polyline;
+
+impl Polyline {
+    /// Returns a transform for a perspective camera that keeps the rotation of `camera` and
+    /// moves it back along its view direction until the whole polyline, placed at `transform`,
+    /// is in view. Returns `None` for polylines without vertices.
+    ///
+    /// The polyline is framed by the sphere around its bounding box, which leaves some margin
+    /// around lines that don't fill their box. The width of the line isn't accounted for.
+    ///
+    /// ```
+    /// # use bevy::{prelude::*, render::camera::PerspectiveProjection};
+    /// # use bevy_polyline::prelude::*;
+    /// let polyline = Polyline::from_points([-Vec3::X, Vec3::X]);
+    /// let projection = PerspectiveProjection {
+    ///     fov: std::f32::consts::FRAC_PI_2,
+    ///     aspect_ratio: 1.0,
+    ///     ..Default::default()
+    /// };
+    /// let camera = polyline
+    ///     .frame_perspective(&GlobalTransform::identity(), &Transform::identity(), &projection)
+    ///     .unwrap();
+    /// assert!((camera.translation.z - 2f32.sqrt()).abs() < 1e-5);
+    /// ```
+    pub fn frame_perspective(
+        &self,
+        transform: &GlobalTransform,
+        camera: &Transform,
+        projection: &PerspectiveProjection,
+    ) -> Option<Transform> {
+        let (center, radius) = self.bounding_sphere(transform)?;
+        let half_fov_y = 0.5 * projection.fov;
+        let half_fov_x = (half_fov_y.tan() * projection.aspect_ratio).atan();
+        let half_fov = half_fov_x.min(half_fov_y);
+        let distance = (radius / half_fov.sin()).max(projection.near);
+        Some(Transform {
+            translation: center - camera.forward() * distance,
+            ..*camera
+        })
+    }
+
+    /// Returns a transform and a [`OrthographicProjection::scale`] for an orthographic camera,
+    /// which keep the rotation of `camera` and center the whole polyline, placed at
+    /// `transform`, in view. Returns `None` for polylines without vertices.
+    ///
+    /// The extents of `projection` are updated from the size of the window by Bevy, so they
+    /// have to be set before the scale can be computed, which is the case after the first
+    /// frame. The camera is moved back far enough for the polyline to be in front of its near
+    /// plane, `projection.far` has to cover at least the size of the polyline.
+    pub fn frame_orthographic(
+        &self,
+        transform: &GlobalTransform,
+        camera: &Transform,
+        projection: &OrthographicProjection,
+    ) -> Option<(Transform, f32)> {
+        let (center, radius) = self.bounding_sphere(transform)?;
+        let width = projection.right - projection.left;
+        let height = projection.top - projection.bottom;
+        let half_extent = 0.5 * width.min(height);
+        // A single point can't be zoomed in on, keep the current scale
+        let scale = if half_extent > 0.0 && radius > 0.0 {
+            radius / half_extent
+        } else {
+            projection.scale
+        };
+        let distance = radius + projection.near.max(0.0);
+        Some((
+            Transform {
+                translation: center - camera.forward() * distance,
+                ..*camera
+            },
+            scale,
+        ))
+    }
+
+    /// Center and radius of the sphere around the bounding box of the polyline, in world space.
+    fn bounding_sphere(&self, transform: &GlobalTransform) -> Option<(Vec3, f32)> {
+        let aabb = self.aabb(0.0)?;
+        let center = transform.mul_vec3(aabb.center);
+        let radius = aabb.half_extents.length() * transform.scale.abs().max_element();
+        Some((center, radius))
+    }
+}
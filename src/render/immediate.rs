@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+
+use crate::{
+    material::PolylineMaterial,
+    polyline::{Polyline, PolylineBundle, PolylineMode},
+    PolylineSystem,
+};
+
+pub struct ImmediateLinesPlugin;
+
+impl Plugin for ImmediateLinesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ImmediateLines>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_immediate_lines.label(PolylineSystem::ImmediateLines),
+            );
+    }
+}
+
+/// Immediate-mode line drawing, mostly useful for debugging: lines added during a frame are drawn
+/// that frame only, without having to manage entities.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_polyline::prelude::*;
+/// fn draw_axes(mut lines: ResMut<ImmediateLines>) {
+///     lines.line(Vec3::ZERO, Vec3::X, Color::RED);
+///     lines.line(Vec3::ZERO, Vec3::Y, Color::GREEN);
+///     lines.line(Vec3::ZERO, Vec3::Z, Color::BLUE);
+/// }
+/// ```
+///
+/// All lines are drawn by a single entity as one [`PolylineMode::LineList`], with per-vertex
+/// colors multiplied by a white material. The material can be restyled through
+/// [`ImmediateLines::material`], and joins don't apply to line lists.
+pub struct ImmediateLines {
+    vertices: Vec<Vec3>,
+    colors: Vec<Color>,
+    polyline: Handle<Polyline>,
+    material: Handle<PolylineMaterial>,
+    /// Whether lines were drawn last frame, so that they get cleared
+    drawn: bool,
+}
+
+/// Marks the entity drawing [`ImmediateLines`]. Its lines change every frame, so it doesn't get
+/// an `Aabb` and isn't frustum culled.
+#[derive(Component)]
+pub struct ImmediateLinesEntity;
+
+impl FromWorld for ImmediateLines {
+    fn from_world(world: &mut World) -> Self {
+        let polyline = world
+            .get_resource_mut::<Assets<Polyline>>()
+            .unwrap()
+            .add(Polyline {
+                mode: PolylineMode::LineList,
+                ..Default::default()
+            });
+        let material = world
+            .get_resource_mut::<Assets<PolylineMaterial>>()
+            .unwrap()
+            .add(PolylineMaterial {
+                width: 2.0,
+                ..Default::default()
+            });
+        world
+            .spawn()
+            .insert_bundle(PolylineBundle {
+                polyline: polyline.clone(),
+                material: material.clone(),
+                ..Default::default()
+            })
+            .insert(ImmediateLinesEntity);
+
+        ImmediateLines {
+            vertices: Vec::new(),
+            colors: Vec::new(),
+            polyline,
+            material,
+            drawn: false,
+        }
+    }
+}
+
+impl ImmediateLines {
+    /// Draws a line from `start` to `end` this frame.
+    pub fn line(&mut self, start: Vec3, end: Vec3, color: Color) {
+        self.line_gradient(start, end, color, color);
+    }
+
+    /// Draws a line from `start` to `end` this frame, with its color fading from `start_color`
+    /// to `end_color`.
+    pub fn line_gradient(&mut self, start: Vec3, end: Vec3, start_color: Color, end_color: Color) {
+        self.vertices.extend([start, end]);
+        self.colors.extend([start_color, end_color]);
+    }
+
+    /// Draws lines connecting consecutive `points` this frame.
+    pub fn polyline(&mut self, points: &[Vec3], color: Color) {
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1], color);
+        }
+    }
+
+    /// The material all immediate lines are drawn with.
+    pub fn material(&self) -> &Handle<PolylineMaterial> {
+        &self.material
+    }
+}
+
+/// Moves the lines drawn this frame into the polyline of the [`ImmediateLinesEntity`].
+pub fn update_immediate_lines(
+    mut lines: ResMut<ImmediateLines>,
+    mut polylines: ResMut<Assets<Polyline>>,
+) {
+    let lines = &mut *lines;
+    let drawn = !lines.vertices.is_empty();
+    // Don't touch the polyline, re-uploading it, while nothing is being drawn
+    if !drawn && !lines.drawn {
+        return;
+    }
+    lines.drawn = drawn;
+
+    if let Some(polyline) = polylines.get_mut(&lines.polyline) {
+        // Swap the buffers, so that both allocations get reused from frame to frame
+        std::mem::swap(&mut polyline.vertices, &mut lines.vertices);
+        std::mem::swap(&mut polyline.colors, &mut lines.colors);
+    }
+    lines.vertices.clear();
+    lines.colors.clear();
+}
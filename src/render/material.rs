@@ -0,0 +1,1514 @@
+use crate::{
+    diagnostics::RenderPolylineStats,
+    polyline::{
+        DrawPolyline, ExtractedPolylineInstances, GpuPolyline, Polyline, Polyline2d,
+        PolylineCamera, PolylineMode, PolylinePipeline, PolylinePipelineKey, PolylineUniform,
+        PolylineViewBindGroup, RenderPolylines, SetPolylineBindGroup, QUAD_VERTEX_COUNT,
+    },
+    SHADER_HANDLE,
+};
+use bevy::{
+    core::FloatOrd,
+    core_pipeline::{AlphaMask3d, Opaque3d, Transparent2d, Transparent3d},
+    ecs::system::{
+        lifetimeless::{Read, SQuery, SRes},
+        SystemParamItem,
+    },
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::ExtractedCamera,
+        render_asset::{PrepareAssetError, RenderAsset, RenderAssetPlugin, RenderAssets},
+        render_component::ExtractComponentPlugin,
+        render_phase::*,
+        render_resource::*,
+        renderer::{RenderDevice, RenderQueue},
+        texture::BevyDefault,
+        view::{ExtractedView, ViewUniformOffset, VisibleEntities},
+        RenderApp, RenderStage,
+    },
+};
+use std::fmt::Debug;
+
+/// The style of a polyline.
+///
+/// Materials are assets referenced by a [`Handle<PolylineMaterial>`], like Bevy's
+/// `StandardMaterial`, so that every line sharing a handle also shares a single uniform buffer
+/// and bind group on the GPU. Lines of many widths can still share one material, see
+/// [`PolylineWidth`](crate::polyline::PolylineWidth).
+///
+/// Materials and [`Polyline`]s inserted as components are saved in Bevy scenes like other
+/// reflected components, except for the `texture`, `colormap` and `fragment_shader` handles.
+/// Serializing a scene needs the `serde` feature for the enums of the material, see
+/// [`PolylineBundle`](crate::polyline::PolylineBundle) for saving a styled polyline.
+///
+/// With the `serde` feature, materials and polylines can also be saved on their own, in any
+/// format supported by serde:
+///
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// # use bevy::prelude::*;
+/// # use bevy_polyline::prelude::*;
+/// let polyline = Polyline {
+///     colors: vec![Color::RED, Color::BLUE, Color::GREEN],
+///     widths: vec![1.0, 2.0, 0.5],
+///     closed: true,
+///     ..Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::Y])
+/// };
+/// let material = PolylineMaterial {
+///     width: 0.25,
+///     width_mode: WidthMode::WorldUnits,
+///     color: Color::rgba(0.2, 0.4, 0.6, 0.8),
+///     join: LineJoin::Round,
+///     dash_length: 0.5,
+///     gap_length: 0.25,
+///     outline: Some((Color::BLACK, 2.0)),
+///     alignment: LineAlignment::Ribbon { normal: Vec3::Y },
+///     ..Default::default()
+/// };
+///
+/// let saved = ron::to_string(&(&polyline, &material)).unwrap();
+/// let (loaded_polyline, loaded_material): (Polyline, PolylineMaterial) =
+///     ron::from_str(&saved).unwrap();
+/// assert_eq!(loaded_polyline, polyline);
+/// assert_eq!(loaded_material, material);
+/// # }
+/// ```
+#[derive(Component, Debug, PartialEq, Clone, TypeUuid, Reflect)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[uuid = "69b87497-2ba0-4c38-ba82-f54bf1ffe873"]
+pub struct PolylineMaterial {
+    /// Width of the line, in screen pixels or in world units depending on `width_mode`. A width
+    /// of `0.0` or less draws nothing.
+    ///
+    /// The conversion to pixels uses the size of the view being rendered, which Bevy updates
+    /// every frame, so lines keep their width when the window is resized, see
+    /// [`PolylineMaterial::pixel_width`]. The line is expanded
+    /// to its width after the entity's transform is applied, so scaling the entity, even
+    /// non-uniformly, moves its vertices without changing its width.
+    pub width: f32,
+    /// The unit of `width`, see [`WidthMode`].
+    pub width_mode: WidthMode,
+    /// Smallest width of the line in pixels, once [`WidthMode::Perspective`] or
+    /// [`WidthMode::WorldUnits`] scaled it with distance. Keeps distant lines from shrinking below a pixel and flickering.
+    /// `None` (the default) doesn't clamp.
+    pub min_width: Option<f32>,
+    /// Largest width of the line in pixels, keeping lines close to the camera from becoming
+    /// absurdly thick. `None` (the default) doesn't clamp.
+    pub max_width: Option<f32>,
+    /// Color of the line. Modifying the material through `Assets<PolylineMaterial>` re-uploads
+    /// its uniform on the next frame, so the change applies to every entity using it.
+    ///
+    /// Like Bevy's `StandardMaterial`, the color can be given in any of Bevy's color spaces and
+    /// is converted to linear RGB before reaching the shader, which blends in linear space.
+    /// `Color::rgb` takes sRGB components, as used by color pickers, so `Color::rgb(0.5, 0.5, 0.5)`
+    /// reaches the shader as about `0.214` and is displayed as the same mid-gray as a PBR
+    /// material with that base color. Per-vertex colors and gradients are converted the same way.
+    pub color: Color,
+    /// Shape of the two ends of an open polyline. Closed polylines have no ends and ignore it,
+    /// while every segment of a [`PolylineMode::LineList`] gets capped.
+    pub cap: LineCap,
+    /// How consecutive segments of a line strip are stitched together.
+    pub join: LineJoin,
+    /// Maximum ratio between the length of a miter and half the line width, beyond which a
+    /// [`LineJoin::Miter`] falls back to a bevel to avoid spikes at sharp turns.
+    pub miter_limit: f32,
+    /// Length of the dashes of a dashed line, measured along the polyline in its local units.
+    pub dash_length: f32,
+    /// Length of the gaps between dashes. A gap of `0.0` (the default) draws a solid line.
+    pub gap_length: f32,
+    /// Shifts the dash pattern forward along the line. Increasing it over time makes the dashes
+    /// flow towards the end of the line. It is wrapped to `dash_length + gap_length` before being
+    /// sent to the GPU, wrapping it on your side as well keeps it from losing precision over
+    /// long runs.
+    pub dash_offset: f32,
+    /// A 16-bit stipple `(pattern, factor)` like the line stipple of legacy OpenGL, for the
+    /// line styles of technical drawings. Starting from the least significant bit, every bit of
+    /// `pattern` covers `factor` units along the line, which is drawn where the bit is set.
+    /// `None` (the default) draws a solid line. It applies on top of dashes.
+    ///
+    /// The units are those of the width: pixels for lines with a width in pixels, local units
+    /// for lines in [`WidthMode::WorldUnits`] or [`WidthMode::Perspective`] and ribbons, so the pattern
+    /// scales along with the width. Pixels are counted from the arc length at the rate of
+    /// the pixel being drawn, which is exact for lines at a constant scale on screen like 2D
+    /// drawings. For lines receding in a perspective view the pattern is only approximate and
+    /// shifts as the camera moves, such lines are better stippled in world units. Unlike
+    /// dashes, the ends of the stipple are never antialiased.
+    pub stipple: Option<(u16, u32)>,
+    /// How the line is composited with what was drawn behind it. A `color` with an alpha below
+    /// `1.0` is always alpha blended, even with [`BlendMode::Opaque`].
+    pub blend: BlendMode,
+    /// Moves the line towards or away from the camera in depth, without changing its size on
+    /// screen. Ranges from `-1.0` to `1.0`: `0.0` leaves the depth untouched, positive values push
+    /// the line back and at `1.0` everything is drawn in front of it, negative values pull it
+    /// forward and at `-1.0` it is drawn on top of everything. Small negative values such as
+    /// `-0.0001` keep lines tracing the edges of a mesh from z-fighting with its faces.
+    ///
+    /// The bias is relative to the depth of the line rather than a distance: positive values move
+    /// the line `depth_bias` of the way from its depth to the far end of the depth range, while
+    /// negative values move it exponentially towards the near plane, which it reaches at `-1.0`.
+    /// It behaves the same regardless of where the camera's planes are.
+    pub depth_bias: f32,
+    /// Smooths the edges of the line by fading out its outermost pixel, which looks crisper than
+    /// MSAA for thin lines. The ends of dashes are faded over a pixel as well. Requires blending,
+    /// so the line is drawn as if its [`BlendMode`] was [`BlendMode::AlphaBlend`] unless it is
+    /// additive.
+    pub antialias: bool,
+    /// Texture mapped along the line and multiplied with its color. Its U coordinate follows the
+    /// length of the line and repeats, its V coordinate goes across the line from one edge to the
+    /// other. Without a texture the line is drawn with its flat or per-vertex color.
+    ///
+    /// Handles only make sense within a running app, so the texture isn't serialized with the
+    /// `serde` feature or in scenes, and has to be set again after loading a material.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[reflect(ignore)]
+    pub texture: Option<Handle<Image>>,
+    /// Number of times the texture repeats per unit of length along the line, in its local units.
+    pub texture_scale: f32,
+    /// Which ends of an open polyline get an arrowhead. Like caps, every segment of a
+    /// [`PolylineMode::LineList`] gets its own arrowheads.
+    pub arrowheads: Arrowheads,
+    /// Length and width of arrowheads, in multiples of the line width.
+    pub arrowhead_size: f32,
+    /// Which vertices get a filled circle drawn on top of the line, see [`Markers`].
+    pub markers: Markers,
+    /// Diameter of markers, in multiples of the line width.
+    pub marker_size: f32,
+    /// Color of markers. `None` (the default) draws them with the color of the line at their
+    /// vertex, including its gradient and per-vertex colors.
+    pub marker_color: Option<Color>,
+    /// Replaces `color` by a gradient from the first color at the start of the line to the
+    /// second color at its end, interpolated by arc length. Per-vertex colors are multiplied with
+    /// the gradient, just like they are with `color`.
+    pub gradient: Option<(Color, Color)>,
+    /// Distance from the camera, along its view direction, at which the line starts fading out.
+    /// Fading is disabled unless `fade_end` is larger than `fade_start`.
+    ///
+    /// Fading requires blending, so the line is drawn as if its [`BlendMode`] was
+    /// [`BlendMode::AlphaBlend`] unless it is additive. With [`WidthMode::Perspective`] distant lines
+    /// also fade as they become thinner than a pixel, the two fades multiply.
+    pub fade_start: f32,
+    /// Distance from the camera at which the line becomes fully transparent.
+    pub fade_end: f32,
+    /// Multiplies the color of the line, letting it exceed `1.0` for emissive glows. The main
+    /// pass currently renders to a low dynamic range target, which clamps the output, so values
+    /// above `1.0` only saturate the line until an HDR target and bloom are available. With
+    /// [`BlendMode::Additive`] it still brightens everything behind the line.
+    pub emissive_intensity: f32,
+    /// Logical layer of the line. At `0` (the default) the line is depth tested against the rest
+    /// of the scene. Lines with a higher `z_order` are drawn on top of everything else in the 3D
+    /// main pass, ignoring the depth buffer, and on top of lines with a lower `z_order`, which
+    /// suits selection highlights and gizmos. Unlike `depth_bias` this doesn't move the line in
+    /// depth, it changes the order it is drawn in.
+    ///
+    /// The main pass draws opaque and alpha masked geometry before transparent geometry, so
+    /// layered lines are queued as transparent regardless of their [`BlendMode`], sorted by their
+    /// `z_order` and then back to front. 2D polylines are already drawn in the order of their z
+    /// translation and ignore it.
+    ///
+    /// To draw lines with a single camera instead, such as a HUD camera, insert a
+    /// [`PolylineCamera`](crate::polyline::PolylineCamera) on them.
+    pub z_order: u32,
+    /// Replaces the crate's fragment shader for custom stroke effects, keeping its vertex layout
+    /// and the expansion of segments into triangles. The shader needs a `fragment` entry point
+    /// taking the outputs of the vertex stage, and gets the same bind groups and shader defs as
+    /// the built-in one, see the README for what they contain. Like `texture`, it isn't
+    /// serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[reflect(ignore)]
+    pub fragment_shader: Option<Handle<Shader>>,
+    /// Which way the line faces, see [`LineAlignment`].
+    pub alignment: LineAlignment,
+    /// Plane `(a, b, c, d)` in world space cutting the line, for section views. Fragments at
+    /// positions `p` with `a * p.x + b * p.y + c * p.z + d < 0.0` are discarded, so the line ends
+    /// exactly where it crosses the plane. Billboarded lines are cut along their center line,
+    /// ribbons along their whole surface. `None` (the default) doesn't clip.
+    ///
+    /// The plane through `point` keeping the side `normal` points to is
+    /// `normal.extend(-normal.dot(point))`.
+    pub clip_plane: Option<Vec4>,
+    /// Number of triangles approximating a [`LineCap::Round`] or [`LineJoin::Round`], trading
+    /// smoothness for vertex count. Defaults to [`ROUND_RESOLUTION`], which looks smooth for lines
+    /// up to a few dozen pixels wide. Values are clamped between `2`, the least that still looks
+    /// rounded, and [`MAX_ROUND_RESOLUTION`], enough for lines hundreds of pixels wide. Distant
+    /// or thin lines can use as few as `4`.
+    pub round_resolution: u32,
+    /// Gradient texture mapping the per-vertex `scalars` of a [`Polyline`] to colors, for
+    /// visualizing data such as speed or temperature along the line. Its U coordinate is the
+    /// scalar normalized to `colormap_range` and clamped, its V coordinate is always `0.5`, so a
+    /// texture one pixel high works as well as any taller one. The sampled color is multiplied
+    /// with the color of the line like `texture` is. Like `texture`, it isn't serialized.
+    ///
+    /// [`Polyline`]: crate::polyline::Polyline
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[reflect(ignore)]
+    pub colormap: Option<Handle<Image>>,
+    /// Scalars mapped to the start and the end of the `colormap`.
+    pub colormap_range: (f32, f32),
+    /// Draws a halo of the given color and width in pixels on both sides of the line, which
+    /// keeps it readable over busy backgrounds. The line is widened by the outline instead of
+    /// being drawn twice, so the outline follows its caps and joins and costs no extra draw
+    /// call. Arrowheads grow with the line but aren't outlined.
+    pub outline: Option<(Color, f32)>,
+    /// Narrows the line to a point at its ends, see [`TaperMode`].
+    pub taper: TaperMode,
+    /// Draws the triangles the line is expanded into instead of the line itself, for debugging
+    /// gaps and overlaps at caps and joins. Each segment gets a color of its own, triangles are
+    /// outlined in white and half transparent, so that overlapping ones look more opaque. The
+    /// transparency needs blending, so lines of an opaque material are moved to the transparent
+    /// pass while it is enabled, which specializes an alpha blended pipeline the first time it
+    /// is toggled on.
+    ///
+    /// Skips dashes, clipping and all other fragment effects. Custom fragment shaders receive
+    /// what the debug view is drawn from as their inputs instead, see the crate's README.
+    pub debug_wireframe: bool,
+}
+
+impl Default for PolylineMaterial {
+    fn default() -> Self {
+        Self {
+            width: 10.0,
+            width_mode: WidthMode::Pixels,
+            min_width: None,
+            max_width: None,
+            color: Color::WHITE,
+            cap: LineCap::Butt,
+            join: LineJoin::None,
+            miter_limit: 4.0,
+            dash_length: 1.0,
+            gap_length: 0.0,
+            dash_offset: 0.0,
+            stipple: None,
+            blend: BlendMode::Opaque,
+            depth_bias: 0.0,
+            antialias: false,
+            texture: None,
+            texture_scale: 1.0,
+            arrowheads: Arrowheads::None,
+            arrowhead_size: 3.0,
+            markers: Markers::None,
+            marker_size: 2.0,
+            marker_color: None,
+            gradient: None,
+            fade_start: 0.0,
+            fade_end: 0.0,
+            emissive_intensity: 1.0,
+            z_order: 0,
+            fragment_shader: None,
+            alignment: LineAlignment::Billboard,
+            clip_plane: None,
+            round_resolution: ROUND_RESOLUTION,
+            colormap: None,
+            colormap_range: (0.0, 1.0),
+            outline: None,
+            taper: TaperMode::None,
+            debug_wireframe: false,
+        }
+    }
+}
+
+/// How a polyline is composited with the scene behind it.
+///
+/// Blended lines are drawn in the transparent pass, sorted back to front by the position of
+/// their entity. Overlapping segments of a single polyline are not sorted against each other,
+/// and blended lines don't write to the depth buffer.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Serialize, Deserialize))]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq))]
+pub enum BlendMode {
+    /// The line replaces what's behind it, and writes to the depth buffer.
+    Opaque,
+    /// The line is blended using its alpha.
+    AlphaBlend,
+    /// The line's color, scaled by its alpha, is added to what's behind it. Overlapping additive
+    /// lines brighten each other regardless of their order, which suits glows and trails.
+    Additive,
+}
+
+impl Default for BlendMode {
+    fn default() -> Self {
+        BlendMode::Opaque
+    }
+}
+
+/// Default number of triangles used to approximate a round cap or join, see
+/// [`PolylineMaterial::round_resolution`].
+pub const ROUND_RESOLUTION: u32 = 8;
+
+/// Largest [`PolylineMaterial::round_resolution`] drawn, higher values are clamped to it.
+pub const MAX_ROUND_RESOLUTION: u32 = 64;
+
+/// The shape drawn at the ends of a polyline.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Serialize, Deserialize))]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq))]
+pub enum LineCap {
+    /// The line ends exactly at its first and last vertex.
+    Butt,
+    /// The line is extended by half of its width past its first and last vertex.
+    Square,
+    /// The line ends in a half circle centered on its first and last vertex.
+    Round,
+}
+
+impl Default for LineCap {
+    fn default() -> Self {
+        LineCap::Butt
+    }
+}
+
+impl LineCap {
+    /// Number of vertices needed to draw a single cap, the shader relies on the same counts.
+    /// Round caps are made of `round_resolution` triangles.
+    pub fn vertex_count(&self, round_resolution: u32) -> u32 {
+        match self {
+            LineCap::Butt => 0,
+            LineCap::Square => QUAD_VERTEX_COUNT,
+            LineCap::Round => 3 * round_resolution,
+        }
+    }
+
+    fn as_u32(&self) -> u32 {
+        match self {
+            LineCap::Butt => 0,
+            LineCap::Square => 1,
+            LineCap::Round => 2,
+        }
+    }
+}
+
+/// The shape filling the gap between two consecutive segments.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Serialize, Deserialize))]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq))]
+pub enum LineJoin {
+    /// Segments are drawn independently, leaving a gap on the outside of turns.
+    None,
+    /// The outer edges of the segments are extended until they meet, limited by
+    /// [`PolylineMaterial::miter_limit`].
+    Miter,
+    /// The outer corners of the segments are connected by a straight edge.
+    Bevel,
+    /// The outer corners of the segments are connected by an arc.
+    Round,
+}
+
+impl Default for LineJoin {
+    fn default() -> Self {
+        LineJoin::None
+    }
+}
+
+impl LineJoin {
+    /// Number of vertices needed to draw a single join, the shader relies on the same counts.
+    /// Round joins are made of `round_resolution` triangles.
+    pub fn vertex_count(&self, round_resolution: u32) -> u32 {
+        match self {
+            LineJoin::None => 0,
+            LineJoin::Miter => QUAD_VERTEX_COUNT,
+            LineJoin::Bevel => 3,
+            LineJoin::Round => 3 * round_resolution,
+        }
+    }
+
+    fn as_u32(&self) -> u32 {
+        match self {
+            LineJoin::None => 0,
+            LineJoin::Miter => 1,
+            LineJoin::Bevel => 2,
+            LineJoin::Round => 3,
+        }
+    }
+}
+
+/// The ends of a polyline decorated with a triangular arrowhead, pointing away from the line along
+/// its first or last segment.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Serialize, Deserialize))]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq))]
+pub enum Arrowheads {
+    None,
+    /// An arrowhead at the first vertex.
+    Start,
+    /// An arrowhead at the last vertex.
+    End,
+    /// Arrowheads at both the first and last vertex.
+    Both,
+}
+
+impl Default for Arrowheads {
+    fn default() -> Self {
+        Arrowheads::None
+    }
+}
+
+impl Arrowheads {
+    /// Number of vertices needed to draw a single arrowhead, the shader relies on the same count.
+    pub const VERTEX_COUNT: u32 = 3;
+
+    pub fn has_start(&self) -> bool {
+        matches!(self, Arrowheads::Start | Arrowheads::Both)
+    }
+
+    pub fn has_end(&self) -> bool {
+        matches!(self, Arrowheads::End | Arrowheads::Both)
+    }
+}
+
+/// The vertices of a polyline marked with a filled circle, for charts showing the samples of a
+/// line along with the line itself. Markers are round whatever the [`LineCap`] and
+/// [`LineJoin`], with as many triangles per half circle as the material's `round_resolution`.
+/// Like arrowheads, they are dashed and clipped with the line at their vertex.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Serialize, Deserialize))]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq))]
+pub enum Markers {
+    None,
+    /// Markers at the first and last vertex of an open polyline, and at both ends of every
+    /// segment of a [`PolylineMode::LineList`]. Closed loops have no ends to mark.
+    Endpoints,
+    /// A marker at every vertex.
+    AllVertices,
+}
+
+impl Default for Markers {
+    fn default() -> Self {
+        Markers::None
+    }
+}
+
+impl Markers {
+    /// Number of vertices needed to draw a single marker, a fan of `2 * round_resolution`
+    /// triangles. The shader relies on the same count.
+    pub fn vertex_count(&self, round_resolution: u32) -> u32 {
+        match self {
+            Markers::None => 0,
+            Markers::Endpoints | Markers::AllVertices => 6 * round_resolution,
+        }
+    }
+}
+
+/// The ends of a polyline its width narrows down to zero towards, for comet tails and motion
+/// trails. The width is scaled by the fraction of the total length of the line, like
+/// [`PolylineMaterial::gradient`], so a [`PolylineMode::LineList`] tapers over all of its
+/// segments rather than each one on its own. Combined with a gradient fading out the alpha the
+/// line fades away as it narrows.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Serialize, Deserialize))]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq))]
+pub enum TaperMode {
+    None,
+    /// The line widens from a point at its first vertex to its full width at its last one.
+    Start,
+    /// The line narrows from its full width at its first vertex to a point at its last one.
+    End,
+    /// The line is widest halfway along its length and narrows towards both ends.
+    Both,
+}
+
+impl Default for TaperMode {
+    fn default() -> Self {
+        TaperMode::None
+    }
+}
+
+impl TaperMode {
+    fn as_u32(&self) -> u32 {
+        match self {
+            TaperMode::None => 0,
+            TaperMode::Start => 1,
+            TaperMode::End => 2,
+            TaperMode::Both => 3,
+        }
+    }
+}
+
+/// The unit a line's width is given in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Serialize, Deserialize))]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq))]
+pub enum WidthMode {
+    /// The width is in screen pixels, taking the size of the view into account, and stays
+    /// constant regardless of the distance to the camera, which suits overlays and CAD-style
+    /// edges.
+    Pixels,
+    /// The width is in screen pixels at the near plane and is divided by the clip-space depth, so
+    /// distant lines become thinner and fade out once they are thinner than a pixel.
+    Perspective,
+    /// The width is in world units, like the size of any other object in the scene, so the line
+    /// becomes thinner with distance with perspective projections and follows the zoom of
+    /// orthographic ones. Lines thinner than a pixel fade out instead of flickering.
+    WorldUnits,
+}
+
+impl Default for WidthMode {
+    fn default() -> Self {
+        WidthMode::Pixels
+    }
+}
+
+/// How the quads making up a thick line are oriented.
+#[derive(Debug, PartialEq, Clone, Copy, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Serialize, Deserialize))]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq))]
+pub enum LineAlignment {
+    /// The line is expanded in screen space, so it faces the camera from every angle and looks
+    /// like a tube. Its `width` is in pixels.
+    Billboard,
+    /// The line is expanded in world space, in the plane perpendicular to `normal`, like a flat
+    /// ribbon. It keeps its orientation when the camera moves around it and gets thinner at
+    /// grazing angles, which suits markings on the ground. Its `width` is always in world units,
+    /// not affected by the entity's scale, regardless of the [`WidthMode`]. Segments parallel to
+    /// `normal` don't cross that plane, and are widened along another direction perpendicular to
+    /// them instead.
+    Ribbon { normal: Vec3 },
+}
+
+impl Default for LineAlignment {
+    fn default() -> Self {
+        LineAlignment::Billboard
+    }
+}
+
+impl PolylineMaterial {
+    /// Width of the line on screen in pixels, `depth` units in front of a camera with the
+    /// `projection` matrix drawing to a view of `view_size` pixels, such as the window. Matches
+    /// the width drawn by the shader before per-entity and per-vertex widths, tapering and
+    /// outlines, for hit testing lines with the cursor.
+    ///
+    /// The shader reads the size of the view every frame, so widths in pixels stay the same when
+    /// the window is resized while widths in world units follow its height:
+    ///
+    /// ```
+    /// # use bevy::{prelude::*, render::camera::CameraProjection};
+    /// # use bevy_polyline::prelude::*;
+    /// let projection = PerspectiveProjection::default().get_projection_matrix();
+    /// let pixels = PolylineMaterial {
+    ///     width: 4.0,
+    ///     ..Default::default()
+    /// };
+    /// let world_units = PolylineMaterial {
+    ///     width: 0.1,
+    ///     width_mode: WidthMode::WorldUnits,
+    ///     ..Default::default()
+    /// };
+    /// let (small, large) = (Vec2::new(800.0, 600.0), Vec2::new(1600.0, 1200.0));
+    /// assert_eq!(pixels.pixel_width(small, projection, 5.0), 4.0);
+    /// assert_eq!(pixels.pixel_width(large, projection, 5.0), 4.0);
+    /// let ratio = world_units.pixel_width(large, projection, 5.0)
+    ///     / world_units.pixel_width(small, projection, 5.0);
+    /// assert!((ratio - 2.0).abs() < 1e-5);
+    /// ```
+    pub fn pixel_width(&self, view_size: Vec2, projection: Mat4, depth: f32) -> f32 {
+        let clip_w = (projection * Vec4::new(0.0, 0.0, -depth, 1.0)).w;
+        let width = match (self.alignment, self.width_mode) {
+            (LineAlignment::Ribbon { .. }, _) | (_, WidthMode::WorldUnits) => {
+                self.width * 0.5 * view_size.y * projection.y_axis.y / clip_w
+            }
+            (_, WidthMode::Perspective) => self.width / clip_w,
+            (_, WidthMode::Pixels) => self.width,
+        };
+        let min_width = self.min_width.unwrap_or(0.0);
+        width.clamp(min_width, self.max_width.unwrap_or(f32::MAX).max(min_width))
+    }
+
+    fn fragment_shader(_asset_server: &AssetServer) -> Handle<Shader> {
+        SHADER_HANDLE.typed()
+    }
+
+    fn vertex_shader(_asset_server: &AssetServer) -> Handle<Shader> {
+        SHADER_HANDLE.typed()
+    }
+
+    pub fn bind_group_layout(render_device: &RenderDevice) -> BindGroupLayout {
+        render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(
+                            PolylineMaterialUniform::min_size().into(),
+                        ),
+                    },
+                    count: None,
+                },
+                // Texture
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // Texture Sampler
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                // Colormap
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // Colormap Sampler
+                BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("polyline_material_layout"),
+        })
+    }
+
+    #[inline]
+    fn bind_group(render_asset: &<Self as RenderAsset>::PreparedAsset) -> &BindGroup {
+        &render_asset.bind_group
+    }
+
+    #[allow(unused_variables)]
+    #[inline]
+    fn dynamic_uniform_indices(material: &<Self as RenderAsset>::PreparedAsset) -> &[u32] {
+        &[]
+    }
+}
+
+/// The uniform a [`PolylineMaterial`] is uploaded as. Colors are converted to linear RGB, which
+/// the shader blends in:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_polyline::{material::PolylineMaterialUniform, prelude::*};
+/// let material = PolylineMaterial {
+///     color: Color::rgb(0.5, 0.5, 0.5),
+///     ..Default::default()
+/// };
+/// let uniform = PolylineMaterialUniform::from(&material);
+/// assert!((uniform.color.x - 0.214).abs() < 1e-3);
+/// assert_eq!(uniform.color.w, 1.0);
+/// ```
+#[derive(ShaderType, Component, Clone)]
+pub struct PolylineMaterialUniform {
+    #[align(16)]
+    pub color: Vec4,
+    pub color_end: Vec4,
+    pub clip_plane: Vec4,
+    pub outline_color: Vec4,
+    pub marker_color: Vec4,
+    pub width: f32,
+    pub min_width: f32,
+    pub max_width: f32,
+    pub cap: u32,
+    pub join: u32,
+    pub round_resolution: u32,
+    pub miter_limit: f32,
+    pub dash_length: f32,
+    pub gap_length: f32,
+    pub dash_offset: f32,
+    pub stipple_pattern: u32,
+    pub stipple_factor: f32,
+    pub stipple_pixels: u32,
+    pub depth_bias: f32,
+    pub texture_scale: f32,
+    pub arrowhead_size: f32,
+    pub marker_size: f32,
+    pub fade_start: f32,
+    pub fade_end: f32,
+    pub emissive_intensity: f32,
+    pub colormap_min: f32,
+    pub colormap_max: f32,
+    pub colormap: u32,
+    pub outline_width: f32,
+    pub taper: u32,
+    pub debug_wireframe: u32,
+    pub ribbon_normal: Vec3,
+}
+
+impl From<&PolylineMaterial> for PolylineMaterialUniform {
+    fn from(material: &PolylineMaterial) -> Self {
+        let (color, color_end) = material
+            .gradient
+            .unwrap_or((material.color, material.color));
+        let round_resolution = material.round_resolution.clamp(2, MAX_ROUND_RESOLUTION);
+        let min_width = material.min_width.unwrap_or(0.0);
+        let (outline_color, outline_width) = material
+            .outline
+            .map_or((Color::NONE, 0.0), |(color, width)| (color, width.max(0.0)));
+        PolylineMaterialUniform {
+            width: material.width,
+            min_width,
+            max_width: material.max_width.unwrap_or(f32::MAX).max(min_width),
+            color: color.as_linear_rgba_f32().into(),
+            color_end: color_end.as_linear_rgba_f32().into(),
+            clip_plane: material.clip_plane.unwrap_or(Vec4::ZERO),
+            outline_color: outline_color.as_linear_rgba_f32().into(),
+            // A negative alpha keeps the color of the line
+            marker_color: material
+                .marker_color
+                .map_or(-Vec4::ONE, |color| color.as_linear_rgba_f32().into()),
+            cap: material.cap.as_u32(),
+            join: material.join.as_u32(),
+            round_resolution,
+            miter_limit: material.miter_limit,
+            dash_length: material.dash_length,
+            gap_length: material.gap_length,
+            dash_offset: {
+                let period = material.dash_length + material.gap_length;
+                if period > 0.0 {
+                    material.dash_offset.rem_euclid(period)
+                } else {
+                    0.0
+                }
+            },
+            stipple_pattern: material.stipple.map_or(0, |(pattern, _)| pattern as u32),
+            stipple_factor: material
+                .stipple
+                .map_or(0.0, |(_, factor)| factor.max(1) as f32),
+            stipple_pixels: (material.width_mode == WidthMode::Pixels
+                && material.alignment == LineAlignment::Billboard)
+                as u32,
+            depth_bias: material.depth_bias.clamp(-1.0, 1.0),
+            texture_scale: material.texture_scale,
+            arrowhead_size: material.arrowhead_size,
+            marker_size: material.marker_size.max(0.0),
+            fade_start: material.fade_start,
+            fade_end: material.fade_end,
+            emissive_intensity: material.emissive_intensity,
+            colormap_min: material.colormap_range.0,
+            colormap_max: material.colormap_range.1,
+            colormap: material.colormap.is_some() as u32,
+            outline_width,
+            taper: material.taper.as_u32(),
+            debug_wireframe: material.debug_wireframe as u32,
+            ribbon_normal: match material.alignment {
+                LineAlignment::Billboard => Vec3::Z,
+                LineAlignment::Ribbon { normal } => normal.normalize_or_zero(),
+            },
+        }
+    }
+}
+
+pub struct GpuPolylineMaterial {
+    pub buffer: Buffer,
+    pub width: f32,
+    pub width_mode: WidthMode,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub round_resolution: u32,
+    pub arrowheads: Arrowheads,
+    pub markers: Markers,
+    pub bind_group: BindGroup,
+    pub alpha_mode: AlphaMode,
+    pub blend: BlendMode,
+    pub antialias: bool,
+    pub z_order: u32,
+    pub fragment_shader: Option<Handle<Shader>>,
+    pub alignment: LineAlignment,
+}
+
+impl RenderAsset for PolylineMaterial {
+    type ExtractedAsset = PolylineMaterial;
+    type PreparedAsset = GpuPolylineMaterial;
+    type Param = (
+        SRes<RenderDevice>,
+        SRes<PolylineMaterialPipeline>,
+        SRes<RenderAssets<Image>>,
+    );
+
+    fn extract_asset(&self) -> Self::ExtractedAsset {
+        self.clone()
+    }
+
+    fn prepare_asset(
+        material: Self::ExtractedAsset,
+        (render_device, polyline_pipeline, gpu_images): &mut bevy::ecs::system::SystemParamItem<
+            Self::Param,
+        >,
+    ) -> Result<
+        Self::PreparedAsset,
+        bevy::render::render_asset::PrepareAssetError<Self::ExtractedAsset>,
+    > {
+        let texture_view = match material.texture.as_ref().map(|handle| gpu_images.get(handle)) {
+            Some(Some(gpu_image)) => &gpu_image.texture_view,
+            Some(None) => return Err(PrepareAssetError::RetryNextUpdate(material)),
+            None => &polyline_pipeline.dummy_white_texture_view,
+        };
+        let colormap_view = match material.colormap.as_ref().map(|handle| gpu_images.get(handle))
+        {
+            Some(Some(gpu_image)) => &gpu_image.texture_view,
+            Some(None) => return Err(PrepareAssetError::RetryNextUpdate(material)),
+            None => &polyline_pipeline.dummy_white_texture_view,
+        };
+
+        let round_resolution = material.round_resolution.clamp(2, MAX_ROUND_RESOLUTION);
+        let value = PolylineMaterialUniform::from(&material);
+
+        let byte_buffer = [0u8;
+            <PolylineMaterialUniform as bevy::render::render_resource::encase::Size>::SIZE.get()
+                as usize];
+        let mut buffer = bevy::render::render_resource::encase::UniformBuffer::new(byte_buffer);
+        buffer
+            .write(&value)
+            .expect("Failed to write to polyline material uniform buffer");
+
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("polyline_material_uniform_buffer"),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            contents: buffer.as_ref(),
+        });
+
+        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(texture_view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(&polyline_pipeline.texture_sampler),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(colormap_view),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: BindingResource::Sampler(&polyline_pipeline.colormap_sampler),
+                },
+            ],
+            label: Some("polyline_material_bind_group"),
+            layout: &polyline_pipeline.material_layout,
+        });
+
+        let translucent = color.a() < 1.0
+            || color_end.a() < 1.0
+            || (outline_width > 0.0 && outline_color.a() < 1.0)
+            || (material.markers != Markers::None
+                && material.marker_color.map_or(false, |color| color.a() < 1.0))
+            || material.fade_end > material.fade_start
+            || material.debug_wireframe;
+        let blend = match material.blend {
+            BlendMode::Opaque if translucent || material.antialias => BlendMode::AlphaBlend,
+            blend => blend,
+        };
+        let alpha_mode = match blend {
+            BlendMode::Opaque => AlphaMode::Opaque,
+            BlendMode::AlphaBlend | BlendMode::Additive => AlphaMode::Blend,
+        };
+
+        Ok(GpuPolylineMaterial {
+            buffer,
+            width: material.width,
+            width_mode: material.width_mode,
+            cap: material.cap,
+            join: material.join,
+            round_resolution,
+            arrowheads: material.arrowheads,
+            markers: material.markers,
+            alpha_mode,
+            blend,
+            antialias: material.antialias,
+            z_order: material.z_order,
+            fragment_shader: material.fragment_shader,
+            alignment: material.alignment,
+            bind_group,
+        })
+    }
+}
+
+/// Adds the necessary ECS resources and render logic to enable rendering entities using ['PolylineMaterial']
+#[derive(Default)]
+pub struct PolylineMaterialPlugin;
+
+impl Plugin for PolylineMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        register_material_types(app);
+        app.add_plugin(ExtractComponentPlugin::<Handle<PolylineMaterial>>::default())
+            .add_plugin(RenderAssetPlugin::<PolylineMaterial>::default());
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .add_render_command::<Transparent3d, DrawMaterial>()
+                .add_render_command::<Opaque3d, DrawMaterial>()
+                .add_render_command::<AlphaMask3d, DrawMaterial>()
+                .add_render_command::<Transparent2d, DrawMaterial>()
+                .init_resource::<PolylineMaterialPipeline>()
+                .init_resource::<SpecializedRenderPipelines<PolylineMaterialPipeline>>()
+                .add_system_to_stage(RenderStage::Queue, queue_material_polylines)
+                .add_system_to_stage(RenderStage::Queue, queue_material_polylines_2d);
+        }
+    }
+}
+
+/// Adds the [`PolylineMaterial`] asset and registers the material's types for reflection, the
+/// main world part of the [`PolylineMaterialPlugin`].
+pub(crate) fn register_material_types(app: &mut App) {
+    app.add_asset::<PolylineMaterial>()
+        .register_type::<PolylineMaterial>()
+        .register_type::<Handle<PolylineMaterial>>()
+        .register_type::<WidthMode>()
+        .register_type::<LineCap>()
+        .register_type::<LineJoin>()
+        .register_type::<BlendMode>()
+        .register_type::<Arrowheads>()
+        .register_type::<Markers>()
+        .register_type::<LineAlignment>()
+        .register_type::<TaperMode>()
+        .register_type::<Option<f32>>()
+        .register_type::<Option<(u16, u32)>>()
+        .register_type::<Option<(Color, Color)>>()
+        .register_type::<Option<(Color, f32)>>()
+        .register_type::<Option<Vec4>>()
+        .register_type::<Option<Color>>();
+}
+
+pub struct PolylineMaterialPipeline {
+    pub polyline_pipeline: PolylinePipeline,
+    pub material_layout: BindGroupLayout,
+    pub vertex_shader: Handle<Shader>,
+    pub fragment_shader: Handle<Shader>,
+    /// Bound in place of the texture of materials that don't have one.
+    pub dummy_white_texture_view: TextureView,
+    /// Repeats along the line and clamps across it.
+    pub texture_sampler: Sampler,
+    /// Clamps scalars outside of the colormap range to its ends.
+    pub colormap_sampler: Sampler,
+    /// Counts the pipelines specialized, see [`PolylineStats::pipelines_specialized`].
+    ///
+    /// [`PolylineStats::pipelines_specialized`]: crate::diagnostics::PolylineStats::pipelines_specialized
+    pub stats: RenderPolylineStats,
+}
+
+impl FromWorld for PolylineMaterialPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        let render_device = world.get_resource::<RenderDevice>().unwrap();
+        let render_queue = world.get_resource::<RenderQueue>().unwrap();
+        let material_layout = PolylineMaterial::bind_group_layout(render_device);
+
+        let dummy_white_image = Image::new_fill(
+            Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[255u8; 4],
+            TextureFormat::bevy_default(),
+        );
+        let texture = render_device.create_texture(&dummy_white_image.texture_descriptor);
+        render_queue.write_texture(
+            ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &dummy_white_image.data,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(std::num::NonZeroU32::new(4).unwrap()),
+                rows_per_image: None,
+            },
+            dummy_white_image.texture_descriptor.size,
+        );
+        let dummy_white_texture_view = texture.create_view(&TextureViewDescriptor::default());
+
+        let texture_sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("polyline_texture_sampler"),
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+        let colormap_sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("polyline_colormap_sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        PolylineMaterialPipeline {
+            polyline_pipeline: world.get_resource::<PolylinePipeline>().unwrap().to_owned(),
+            material_layout,
+            vertex_shader: PolylineMaterial::vertex_shader(asset_server),
+            fragment_shader: PolylineMaterial::fragment_shader(asset_server),
+            dummy_white_texture_view,
+            texture_sampler,
+            colormap_sampler,
+            stats: world
+                .get_resource::<RenderPolylineStats>()
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Specializes the [`PolylineMaterialPipeline`] for the flags of a [`PolylinePipelineKey`] and the
+/// fragment shader of a material.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PolylineMaterialPipelineKey {
+    pub polyline_key: PolylinePipelineKey,
+    pub fragment_shader: Option<Handle<Shader>>,
+}
+
+impl SpecializedRenderPipeline for PolylineMaterialPipeline {
+    type Key = PolylineMaterialPipelineKey;
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        // Only called for keys missing from the cache of `SpecializedRenderPipelines`
+        self.stats.add_pipeline_specialized();
+        let PolylineMaterialPipelineKey {
+            polyline_key: key,
+            fragment_shader,
+        } = key;
+        let mut descriptor = self.polyline_pipeline.specialize(key);
+        if key.contains(PolylinePipelineKey::PERSPECTIVE) {
+            descriptor
+                .vertex
+                .shader_defs
+                .push("POLYLINE_PERSPECTIVE".to_string());
+        }
+        if key.contains(PolylinePipelineKey::WORLD_WIDTH) {
+            descriptor
+                .vertex
+                .shader_defs
+                .push("POLYLINE_WORLD_WIDTH".to_string());
+        }
+        // Both shrink lines with distance, fade them once they are thinner than a pixel
+        if key.intersects(PolylinePipelineKey::PERSPECTIVE | PolylinePipelineKey::WORLD_WIDTH) {
+            descriptor
+                .vertex
+                .shader_defs
+                .push("POLYLINE_THIN_FADE".to_string());
+        }
+        if key.contains(PolylinePipelineKey::RIBBON) {
+            descriptor
+                .vertex
+                .shader_defs
+                .push("POLYLINE_RIBBON".to_string());
+        }
+        if key.contains(PolylinePipelineKey::ANTIALIAS) {
+            descriptor
+                .vertex
+                .shader_defs
+                .push("POLYLINE_ANTIALIAS".to_string());
+            descriptor
+                .fragment
+                .as_mut()
+                .unwrap()
+                .shader_defs
+                .push("POLYLINE_ANTIALIAS".to_string());
+        }
+        if let Some(fragment_shader) = fragment_shader {
+            descriptor.fragment.as_mut().unwrap().shader = fragment_shader;
+        }
+        let mut layout = vec![
+            self.polyline_pipeline.view_layout.clone(),
+            self.polyline_pipeline.polyline_layout.clone(),
+            self.material_layout.clone(),
+        ];
+        #[cfg(not(feature = "webgl"))]
+        if key.intersects(PolylinePipelineKey::INDEXED | PolylinePipelineKey::INSTANCED) {
+            layout.push(self.polyline_pipeline.storage_layout.clone());
+        }
+        descriptor.layout = Some(layout);
+        descriptor
+    }
+}
+
+type DrawMaterial = (
+    SetItemPipeline,
+    SetPolylineViewBindGroup<0>,
+    SetPolylineBindGroup<1>,
+    SetMaterialBindGroup<2>,
+    DrawPolyline,
+);
+
+pub struct SetPolylineViewBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetPolylineViewBindGroup<I> {
+    type Param = SQuery<(
+        Read<ViewUniformOffset>,
+        //Read<ViewLightsUniformOffset>,
+        Read<PolylineViewBindGroup>,
+    )>;
+    #[inline]
+    fn render<'w>(
+        view: Entity,
+        _item: Entity,
+        view_query: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let (view_uniform, mesh_view_bind_group) = view_query.get_inner(view).unwrap();
+        pass.set_bind_group(
+            I,
+            &mesh_view_bind_group.value,
+            &[view_uniform.offset], //, view_lights.offset],
+        );
+
+        RenderCommandResult::Success
+    }
+}
+
+pub struct SetMaterialBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetMaterialBindGroup<I> {
+    type Param = (
+        SRes<RenderAssets<PolylineMaterial>>,
+        SQuery<Read<Handle<PolylineMaterial>>>,
+    );
+    fn render<'w>(
+        _view: Entity,
+        item: Entity,
+        (materials, query): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let material_handle = query.get(item).unwrap();
+        let material = materials.into_inner().get(material_handle).unwrap();
+        pass.set_bind_group(
+            I,
+            PolylineMaterial::bind_group(material),
+            PolylineMaterial::dynamic_uniform_indices(material),
+        );
+        RenderCommandResult::Success
+    }
+}
+
+/// Alpha mode an entity's polyline is drawn with, which blends if its
+/// [`PolylineOpacity`](crate::polyline::PolylineOpacity) makes it translucent.
+fn alpha_mode(material: &GpuPolylineMaterial, polyline_uniform: &PolylineUniform) -> AlphaMode {
+    if polyline_uniform.opacity < 1.0 {
+        AlphaMode::Blend
+    } else {
+        material.alpha_mode
+    }
+}
+
+/// Computes the pipeline key of a polyline drawn with `material`, as copies of
+/// [`ExtractedPolylineInstances`] if `instanced`.
+#[cfg_attr(feature = "webgl", allow(unused_variables))]
+fn polyline_pipeline_key(
+    msaa_key: PolylinePipelineKey,
+    material: &GpuPolylineMaterial,
+    alpha_mode: AlphaMode,
+    polyline: &GpuPolyline,
+    instanced: bool,
+) -> PolylineMaterialPipelineKey {
+    let mut polyline_key = msaa_key;
+    if alpha_mode == AlphaMode::Blend {
+        polyline_key |= PolylinePipelineKey::TRANSPARENT_MAIN_PASS
+    }
+    if material.blend == BlendMode::Additive {
+        polyline_key |= PolylinePipelineKey::ADDITIVE
+    }
+    if material.antialias {
+        polyline_key |= PolylinePipelineKey::ANTIALIAS
+    }
+    match (material.alignment, material.width_mode) {
+        (LineAlignment::Ribbon { .. }, _) => {
+            polyline_key |= PolylinePipelineKey::RIBBON | PolylinePipelineKey::WORLD_WIDTH
+        }
+        (LineAlignment::Billboard, WidthMode::WorldUnits) => {
+            polyline_key |= PolylinePipelineKey::WORLD_WIDTH
+        }
+        (LineAlignment::Billboard, WidthMode::Perspective) => {
+            polyline_key |= PolylinePipelineKey::PERSPECTIVE
+        }
+        (LineAlignment::Billboard, WidthMode::Pixels) => {}
+    }
+    if polyline.mode == PolylineMode::LineList {
+        polyline_key |= PolylinePipelineKey::LINE_LIST
+    }
+    if polyline.indices.is_some() {
+        polyline_key |= PolylinePipelineKey::INDEXED
+    }
+    // WebGL2 draws every copy separately from the vertex buffer
+    #[cfg(not(feature = "webgl"))]
+    if instanced {
+        polyline_key |= PolylinePipelineKey::INSTANCED
+    }
+    if material.z_order > 0 && !msaa_key.contains(PolylinePipelineKey::VIEW_2D) {
+        polyline_key |= PolylinePipelineKey::ON_TOP
+    }
+    PolylineMaterialPipelineKey {
+        polyline_key,
+        fragment_shader: material.fragment_shader.clone(),
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn queue_material_polylines(
+    mut warned_degenerate: Local<bool>,
+    opaque_draw_functions: Res<DrawFunctions<Opaque3d>>,
+    alpha_mask_draw_functions: Res<DrawFunctions<AlphaMask3d>>,
+    transparent_draw_functions: Res<DrawFunctions<Transparent3d>>,
+    material_pipeline: Res<PolylineMaterialPipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<PolylineMaterialPipeline>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    msaa: Res<Msaa>,
+    render_materials: Res<RenderAssets<PolylineMaterial>>,
+    render_polylines: Res<RenderPolylines>,
+    material_meshes: Query<
+        (
+            &Handle<PolylineMaterial>,
+            &Handle<Polyline>,
+            &PolylineUniform,
+            Option<&PolylineCamera>,
+            Option<&ExtractedPolylineInstances>,
+        ),
+        Without<Polyline2d>,
+    >,
+    mut views: Query<(
+        &ExtractedView,
+        Option<&ExtractedCamera>,
+        &VisibleEntities,
+        &mut RenderPhase<Opaque3d>,
+        &mut RenderPhase<AlphaMask3d>,
+        &mut RenderPhase<Transparent3d>,
+    )>,
+) {
+    let draw_opaque = opaque_draw_functions
+        .read()
+        .get_id::<DrawMaterial>()
+        .unwrap();
+    let draw_alpha_mask = alpha_mask_draw_functions
+        .read()
+        .get_id::<DrawMaterial>()
+        .unwrap();
+    let draw_transparent = transparent_draw_functions
+        .read()
+        .get_id::<DrawMaterial>()
+        .unwrap();
+    let msaa_key = PolylinePipelineKey::from_msaa_samples(msaa.samples);
+
+    for (
+        view,
+        camera,
+        visible_entities,
+        mut opaque_phase,
+        mut alpha_mask_phase,
+        mut transparent_phase,
+    ) in views.iter_mut()
+    {
+        let inverse_view_matrix = view.transform.compute_matrix().inverse();
+        let inverse_view_row_2 = inverse_view_matrix.row(2);
+
+        for visible_entity in &visible_entities.entities {
+            if let Ok((material_handle, polyline_handle, polyline_uniform, target, instances)) =
+                material_meshes.get(*visible_entity)
+            {
+                if !PolylineCamera::targets(target, camera) {
+                    continue;
+                }
+                if let (Some(material), Some(polyline)) = (
+                    render_materials.get(material_handle),
+                    render_polylines.get(polyline_handle),
+                ) {
+                    // A zero (or negative) width line has no visible area, and a line of zero
+                    // opacity, not revealed yet or without any instance nothing to show, skip
+                    // them entirely instead of drawing nothing.
+                    if polyline_uniform.width(material.width) <= 0.0
+                        || polyline_uniform.opacity <= 0.0
+                        || polyline_uniform.reveal <= 0.0
+                        || instances.map_or(false, |instances| instances.0.is_empty())
+                    {
+                        continue;
+                    }
+                    // Fewer than two vertices (or a lone vertex in list mode) make no segment.
+                    // Empty polylines are expected, e.g. for trails that haven't started yet.
+                    if polyline.segment_count() == 0 {
+                        if polyline.vertex_count > 0 && !*warned_degenerate {
+                            warn!(
+                                "Skipping polyline on {:?} with too few vertices to draw a segment, \
+                                this warning is only logged once",
+                                visible_entity
+                            );
+                            *warned_degenerate = true;
+                        }
+                        continue;
+                    }
+                    // Pipelines are cached by key, so this only creates a new pipeline the
+                    // first time a combination of material settings, polyline mode and MSAA
+                    // sample count is drawn, as counted by
+                    // `PolylineStats::pipelines_specialized`. The sample count is part of the key, so all lines
+                    // switch to the pipelines of a new `Msaa` in the frame it changes, and lines
+                    // whose new pipeline is still compiling are skipped like meshes rather than
+                    // drawn with the old one.
+                    let alpha_mode = alpha_mode(material, polyline_uniform);
+                    let polyline_key = polyline_pipeline_key(
+                        msaa_key,
+                        material,
+                        alpha_mode,
+                        polyline,
+                        instances.is_some(),
+                    );
+                    let pipeline_id =
+                        pipelines.specialize(&mut pipeline_cache, &material_pipeline, polyline_key);
+
+                    // NOTE: row 2 of the inverse view matrix dotted with column 3 of the model matrix
+                    // gives the z component of translation of the mesh in view space
+                    let polyline_z = inverse_view_row_2.dot(polyline_uniform.transform.col(3));
+                    if material.z_order > 0 {
+                        transparent_phase.add(Transparent3d {
+                            entity: *visible_entity,
+                            draw_function: draw_transparent,
+                            pipeline: pipeline_id,
+                            // NOTE: Maps the view space z of everything in front of the camera
+                            // into the range (0, 0.5], which increases towards the camera. Adding
+                            // the z order sorts layers above everything in the scene, which has
+                            // negative distances, and above lower layers, while keeping lines of
+                            // the same layer ordered back to front
+                            distance: material.z_order as f32
+                                + 0.5 * (1.0 + polyline_z.min(0.0) / (1.0 - polyline_z.min(0.0))),
+                        });
+                        continue;
+                    }
+                    match alpha_mode {
+                        AlphaMode::Opaque => {
+                            opaque_phase.add(Opaque3d {
+                                entity: *visible_entity,
+                                draw_function: draw_opaque,
+                                pipeline: pipeline_id,
+                                // NOTE: Front-to-back ordering for opaque with ascending sort means near should have the
+                                // lowest sort key and getting further away should increase. As we have
+                                // -z in front of the camera, values in view space decrease away from the
+                                // camera. Flipping the sign of mesh_z results in the correct front-to-back ordering
+                                distance: -polyline_z,
+                            });
+                        }
+                        AlphaMode::Mask(_) => {
+                            alpha_mask_phase.add(AlphaMask3d {
+                                entity: *visible_entity,
+                                draw_function: draw_alpha_mask,
+                                pipeline: pipeline_id,
+                                // NOTE: Front-to-back ordering for alpha mask with ascending sort means near should have the
+                                // lowest sort key and getting further away should increase. As we have
+                                // -z in front of the camera, values in view space decrease away from the
+                                // camera. Flipping the sign of mesh_z results in the correct front-to-back ordering
+                                distance: -polyline_z,
+                            });
+                        }
+                        AlphaMode::Blend => {
+                            transparent_phase.add(Transparent3d {
+                                entity: *visible_entity,
+                                draw_function: draw_transparent,
+                                pipeline: pipeline_id,
+                                // NOTE: Back-to-front ordering for transparent with ascending sort means far should have the
+                                // lowest sort key and getting closer should increase. As we have
+                                // -z in front of the camera, the largest distance is -far with values increasing toward the
+                                // camera. As such we can just use mesh_z as the distance
+                                distance: polyline_z,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn queue_material_polylines_2d(
+    transparent_draw_functions: Res<DrawFunctions<Transparent2d>>,
+    material_pipeline: Res<PolylineMaterialPipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<PolylineMaterialPipeline>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    msaa: Res<Msaa>,
+    render_materials: Res<RenderAssets<PolylineMaterial>>,
+    render_polylines: Res<RenderPolylines>,
+    material_meshes: Query<
+        (
+            &Handle<PolylineMaterial>,
+            &Handle<Polyline>,
+            &PolylineUniform,
+            Option<&PolylineCamera>,
+            Option<&ExtractedPolylineInstances>,
+        ),
+        With<Polyline2d>,
+    >,
+    mut views: Query<(
+        Option<&ExtractedCamera>,
+        &VisibleEntities,
+        &mut RenderPhase<Transparent2d>,
+    )>,
+) {
+    let draw_transparent = transparent_draw_functions
+        .read()
+        .get_id::<DrawMaterial>()
+        .unwrap();
+    let msaa_key =
+        PolylinePipelineKey::from_msaa_samples(msaa.samples) | PolylinePipelineKey::VIEW_2D;
+
+    for (camera, visible_entities, mut transparent_phase) in views.iter_mut() {
+        for visible_entity in &visible_entities.entities {
+            if let Ok((material_handle, polyline_handle, polyline_uniform, target, instances)) =
+                material_meshes.get(*visible_entity)
+            {
+                if !PolylineCamera::targets(target, camera) {
+                    continue;
+                }
+                if let (Some(material), Some(polyline)) = (
+                    render_materials.get(material_handle),
+                    render_polylines.get(polyline_handle),
+                ) {
+                    if polyline_uniform.width(material.width) <= 0.0
+                        || polyline_uniform.opacity <= 0.0
+                        || polyline_uniform.reveal <= 0.0
+                        || instances.map_or(false, |instances| instances.0.is_empty())
+                        || polyline.segment_count() == 0
+                    {
+                        continue;
+                    }
+                    let alpha_mode = alpha_mode(material, polyline_uniform);
+                    let polyline_key = polyline_pipeline_key(
+                        msaa_key,
+                        material,
+                        alpha_mode,
+                        polyline,
+                        instances.is_some(),
+                    );
+                    let pipeline_id =
+                        pipelines.specialize(&mut pipeline_cache, &material_pipeline, polyline_key);
+
+                    transparent_phase.add(Transparent2d {
+                        entity: *visible_entity,
+                        draw_function: draw_transparent,
+                        pipeline: pipeline_id,
+                        // NOTE: 2D items are drawn in ascending order of their z translation, so
+                        // higher z values end up on top
+                        sort_key: FloatOrd(polyline_uniform.transform.w_axis.z),
+                        batch_range: None,
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, VertexAttributeValues},
+        render_resource::PrimitiveTopology,
+    },
+    utils::HashSet,
+};
+
+use crate::polyline::{Polyline, PolylineMode};
+
+impl Polyline {
+    /// Creates a line list of the unique edges of a mesh, for debugging its topology. Works
+    /// with indexed and non-indexed meshes of any topology but points, and an edge shared by
+    /// several triangles is only drawn once.
+    ///
+    /// Edges are compared by the positions of their ends, so edges of triangles that don't share
+    /// vertices, like the faces of a cube or a non-indexed mesh, are deduplicated as well. Meshes
+    /// without [`Mesh::ATTRIBUTE_POSITION`] produce an empty polyline.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let cube = Mesh::from(shape::Cube::new(1.0));
+    /// let edges = Polyline::from_mesh_edges(&cube);
+    /// // The 12 edges of the cube and the diagonal of each face
+    /// assert_eq!(edges.vertices.len(), 18 * 2);
+    /// ```
+    pub fn from_mesh_edges(mesh: &Mesh) -> Self {
+        let mut edges = Polyline {
+            mode: PolylineMode::LineList,
+            ..Default::default()
+        };
+        let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => positions,
+            _ => return edges,
+        };
+        let indices: Vec<usize> = match mesh.indices() {
+            Some(indices) => indices.iter().collect(),
+            None => (0..positions.len()).collect(),
+        };
+
+        let pairs: Vec<(usize, usize)> = match mesh.primitive_topology() {
+            PrimitiveTopology::PointList => Vec::new(),
+            PrimitiveTopology::LineList => indices
+                .chunks_exact(2)
+                .map(|pair| (pair[0], pair[1]))
+                .collect(),
+            PrimitiveTopology::LineStrip => indices
+                .windows(2)
+                .map(|pair| (pair[0], pair[1]))
+                .collect(),
+            PrimitiveTopology::TriangleList => indices
+                .chunks_exact(3)
+                .flat_map(|tri| [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])])
+                .collect(),
+            PrimitiveTopology::TriangleStrip => indices
+                .windows(3)
+                .flat_map(|tri| [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])])
+                .collect(),
+        };
+
+        // Positions are compared bitwise, which is exact for vertices duplicated from the same
+        // position
+        let key = |i: usize| positions[i].map(f32::to_bits);
+        let mut seen = HashSet::default();
+        for (a, b) in pairs {
+            if a >= positions.len() || b >= positions.len() {
+                continue;
+            }
+            let (key_a, key_b) = (key(a), key(b));
+            if key_a == key_b {
+                continue;
+            }
+            if seen.insert((key_a.min(key_b), key_a.max(key_b))) {
+                edges
+                    .vertices
+                    .extend([Vec3::from(positions[a]), Vec3::from(positions[b])]);
+            }
+        }
+        edges
+    }
+
+    /// Builds a triangle mesh of a tube of `radius` around the polyline, with `radial_segments`
+    /// faces around its circumference, for drawing lines as solid geometry with Bevy's own
+    /// materials and lighting. Per-vertex widths scale the radius, and the ends of open lines
+    /// are closed with flat caps. The U texture coordinate goes around the tube, the V
+    /// coordinate is the arc length, like in [`PolylineMaterial::texture`].
+    ///
+    /// The cross-section is carried along the line without twisting, so textures don't spin
+    /// around it. At bends it is stretched along the direction of the bend, like a miter, to
+    /// keep the tube from pinching, up to four times the radius for the sharpest turns. Each
+    /// segment of a [`PolylineMode::LineList`] is a separate tube, and duplicate vertices are
+    /// skipped, see [`Polyline::deduplicated`], along with segments of a list whose endpoints are
+    /// equal.
+    ///
+    /// [`PolylineMaterial::texture`]: crate::material::PolylineMaterial::texture
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let polyline = Polyline::from_points([Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0)]);
+    /// let tube = polyline.to_tube_mesh(0.1, 8);
+    /// // Three rings of eight segments, each repeating its first vertex at the texture seam,
+    /// // and two caps of a center vertex and one vertex per segment
+    /// assert_eq!(tube.count_vertices(), 3 * 9 + 2 * 9);
+    ///
+    /// // The second segment of this list has no length and no tube
+    /// let list = Polyline {
+    ///     vertices: vec![Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::Y],
+    ///     mode: PolylineMode::LineList,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(list.to_tube_mesh(0.1, 8).count_vertices(), 2 * 9 + 2 * 9);
+    /// ```
+    pub fn to_tube_mesh(&self, radius: f32, radial_segments: u32) -> Mesh {
+        let polyline = self.deduplicated();
+        let radial_segments = radial_segments.max(3) as usize;
+        let n = polyline.vertices.len();
+        let pieces: Vec<(Vec<usize>, bool)> = match polyline.mode {
+            PolylineMode::LineStrip => vec![((0..n).collect(), polyline.is_closed())],
+            PolylineMode::LineList => (0..n / 2)
+                .map(|k| (vec![2 * k, 2 * k + 1], false))
+                .collect(),
+        };
+        let mut tube = TubeBuilder::default();
+        for (piece, closed) in pieces {
+            tube.push_tube(&polyline, &piece, closed, radius, radial_segments);
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, tube.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, tube.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, tube.uvs);
+        mesh.set_indices(Some(Indices::U32(tube.indices)));
+        mesh
+    }
+}
+
+/// Vertex data of the mesh built by [`Polyline::to_tube_mesh`].
+#[derive(Default)]
+struct TubeBuilder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl TubeBuilder {
+    /// Adds the tube around the vertices of `polyline` at the indices `piece`. Consecutive
+    /// duplicates are skipped, since zero length segments have no direction to orient the tube,
+    /// and pieces left with a single point add nothing.
+    fn push_tube(
+        &mut self,
+        polyline: &Polyline,
+        piece: &[usize],
+        closed: bool,
+        radius: f32,
+        radial_segments: usize,
+    ) {
+        let mut piece = piece.to_vec();
+        piece.dedup_by_key(|i| polyline.vertices[*i]);
+        if closed
+            && piece.len() > 1
+            && polyline.vertices[piece[0]] == polyline.vertices[piece[piece.len() - 1]]
+        {
+            piece.pop();
+        }
+        let points: Vec<Vec3> = piece.iter().map(|&i| polyline.vertices[i]).collect();
+        let m = points.len();
+        if m < 2 {
+            return;
+        }
+        let segment_count = if closed { m } else { m - 1 };
+        let directions: Vec<Vec3> = (0..segment_count)
+            .map(|i| (points[(i + 1) % m] - points[i]).normalize_or_zero())
+            .collect();
+
+        // Closed loops repeat their first ring at the end, so the texture wraps around
+        let ring_count = if closed { m + 1 } else { m };
+        let mut rings = Vec::with_capacity(ring_count);
+        let mut arc_length = 0.0;
+        for i in 0..ring_count {
+            if i > 0 {
+                arc_length += points[i % m].distance(points[i - 1]);
+            }
+            let incoming = match i {
+                0 if closed => directions[segment_count - 1],
+                0 => directions[0],
+                _ => directions[i - 1],
+            };
+            let outgoing = if i < segment_count {
+                directions[i]
+            } else if closed {
+                directions[0]
+            } else {
+                directions[segment_count - 1]
+            };
+            // Lines doubling back on themselves have no bisector, the ring then faces the
+            // incoming segment
+            let mut tangent = (incoming + outgoing).normalize_or_zero();
+            if tangent == Vec3::ZERO {
+                tangent = incoming;
+            }
+            let bend = outgoing - incoming;
+            rings.push(Ring {
+                point: points[i % m],
+                tangent,
+                normal: Vec3::ZERO,
+                bend: (bend - tangent * bend.dot(tangent)).normalize_or_zero(),
+                stretch: 1.0 / incoming.dot(tangent).max(0.25),
+                radius: radius * polyline.width_at(piece[i % m]),
+                arc_length,
+            });
+        }
+
+        // Parallel transport of the normal from ring to ring, which doesn't twist the tube
+        let first_tangent = rings[0].tangent;
+        let reference = if first_tangent.x.abs() < 0.9 {
+            Vec3::X
+        } else {
+            Vec3::Y
+        };
+        rings[0].normal = first_tangent.cross(reference).normalize();
+        for i in 1..ring_count {
+            let (previous, ring) = (&rings[i - 1], &rings[i]);
+            let normal = Quat::from_rotation_arc(previous.tangent, ring.tangent) * previous.normal;
+            rings[i].normal = (normal - ring.tangent * normal.dot(ring.tangent)).normalize();
+        }
+        if closed {
+            // Transporting the normal around a loop can leave it rotated, spread the rotation
+            // along the loop so the last ring lines up with the first one
+            let (first, last) = (rings[0].normal, rings[ring_count - 1].normal);
+            let twist = last.cross(first).dot(first_tangent).atan2(last.dot(first));
+            for (i, ring) in rings.iter_mut().enumerate() {
+                let rotation = Quat::from_axis_angle(ring.tangent, twist * i as f32 / m as f32);
+                ring.normal = rotation * ring.normal;
+            }
+        }
+
+        // Rings have an extra vertex where the texture wraps around
+        let ring_size = radial_segments + 1;
+        let start = self.positions.len() as u32;
+        for ring in &rings {
+            for j in 0..ring_size {
+                let direction = ring.direction(j, radial_segments);
+                self.positions.push(ring.position(direction).to_array());
+                self.normals.push(direction.to_array());
+                let u = j as f32 / radial_segments as f32;
+                self.uvs.push([u, ring.arc_length]);
+            }
+        }
+        for i in 0..ring_count as u32 - 1 {
+            for j in 0..radial_segments as u32 {
+                let a = start + i * ring_size as u32 + j;
+                let c = a + ring_size as u32;
+                self.indices.extend([a, a + 1, c, a + 1, c + 1, c]);
+            }
+        }
+
+        if !closed {
+            self.push_cap(&rings[0], -rings[0].tangent, radial_segments);
+            let last = &rings[ring_count - 1];
+            self.push_cap(last, last.tangent, radial_segments);
+        }
+    }
+
+    /// Adds a flat cap closing the tube at `ring`, facing `normal`.
+    fn push_cap(&mut self, ring: &Ring, normal: Vec3, radial_segments: usize) {
+        let center = self.positions.len() as u32;
+        self.positions.push(ring.point.to_array());
+        self.normals.push(normal.to_array());
+        self.uvs.push([0.5, 0.5]);
+        for j in 0..radial_segments {
+            let direction = ring.direction(j, radial_segments);
+            self.positions.push(ring.position(direction).to_array());
+            self.normals.push(normal.to_array());
+            let binormal = ring.tangent.cross(ring.normal);
+            let (u, v) = (direction.dot(ring.normal), direction.dot(binormal));
+            self.uvs.push([0.5 + 0.5 * u, 0.5 + 0.5 * v]);
+        }
+        // Ring directions turn counterclockwise around the tangent
+        let facing_forward = normal.dot(ring.tangent) > 0.0;
+        for j in 0..radial_segments as u32 {
+            let a = center + 1 + j;
+            let b = center + 1 + (j + 1) % radial_segments as u32;
+            if facing_forward {
+                self.indices.extend([center, a, b]);
+            } else {
+                self.indices.extend([center, b, a]);
+            }
+        }
+    }
+}
+
+/// A cross-section of the tube built by [`Polyline::to_tube_mesh`], at a vertex of the line.
+struct Ring {
+    point: Vec3,
+    /// Normal of the plane of the ring, halfway between the directions of the segments before
+    /// and after the vertex.
+    tangent: Vec3,
+    /// Direction of the first vertex of the ring from its center.
+    normal: Vec3,
+    /// Direction the line turns towards, in the plane of the ring, along which it is stretched.
+    bend: Vec3,
+    stretch: f32,
+    radius: f32,
+    arc_length: f32,
+}
+
+impl Ring {
+    /// Direction of the `j`th of `radial_segments` vertices of the ring from its center.
+    fn direction(&self, j: usize, radial_segments: usize) -> Vec3 {
+        let angle = j as f32 / radial_segments as f32 * std::f32::consts::TAU;
+        let binormal = self.tangent.cross(self.normal);
+        self.normal * angle.cos() + binormal * angle.sin()
+    }
+
+    /// Position of the vertex of the ring in `direction`, stretched along the bend.
+    fn position(&self, direction: Vec3) -> Vec3 {
+        let offset = direction * self.radius;
+        self.point + offset + self.bend * offset.dot(self.bend) * (self.stretch - 1.0)
+    }
+}
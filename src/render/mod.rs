@@ -0,0 +1,136 @@
+use bevy::{prelude::*, reflect::TypeUuid, render::RenderApp};
+
+use crate::PolylinePluginBuilder;
+use bounds::PolylineBoundsPlugin;
+use diagnostics::{PolylineDiagnosticsPlugin, PolylineStatsPlugin};
+use immediate::ImmediateLinesPlugin;
+use material::PolylineMaterialPlugin;
+use pipeline::PolylineRenderPlugin;
+
+pub(crate) mod bounds;
+pub(crate) mod bundle;
+pub mod diagnostics;
+mod framing;
+pub mod immediate;
+pub mod material;
+mod mesh;
+pub(crate) mod pipeline;
+
+pub mod prelude {
+    pub use super::bundle::{
+        Polyline2d, Polyline2dBundle, PolylineBundle, PolylineBundleBuilder, PolylineCamera,
+        SpawnPolylineExt,
+    };
+    pub use super::diagnostics::{PolylineBufferUpdated, PolylineStats};
+    pub use super::immediate::ImmediateLines;
+    pub use super::material::{
+        Arrowheads, BlendMode, LineAlignment, LineCap, LineJoin, Markers, PolylineMaterial,
+        TaperMode, WidthMode,
+    };
+    pub use super::pipeline::PolylineVertexUpdates;
+}
+
+pub const SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 12823766040132746065);
+
+/// Present in the render world if polylines are cleaned of non-finite vertices before they are
+/// uploaded, see [`PolylinePluginBuilder::with_vertex_sanitization`].
+pub(crate) struct SanitizeVertices;
+
+/// Shader loaded from the crate's sources, copied to [`SHADER_HANDLE`] whenever it changes.
+#[cfg(feature = "shader_hot_reload")]
+struct HotReloadedShader(Handle<Shader>);
+
+#[cfg(feature = "shader_hot_reload")]
+fn hot_reload_shader(
+    mut events: EventReader<AssetEvent<Shader>>,
+    source: Res<HotReloadedShader>,
+    mut shaders: ResMut<Assets<Shader>>,
+) {
+    for event in events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            if *handle == source.0 {
+                if let Some(shader) = shaders.get(handle).cloned() {
+                    shaders.set_untracked(SHADER_HANDLE, shader);
+                }
+            }
+        }
+    }
+}
+
+impl PolylinePluginBuilder {
+    /// Leaves out the crate's shader, pipelines and render world systems, for apps drawing
+    /// polylines with a pipeline of their own. `Polyline` and `PolylineMaterial` assets, the
+    /// bounds of polylines and [`ImmediateLines`](immediate::ImmediateLines) are still kept up to
+    /// date in the main world, to be extracted by the app. The [`PolylineStats`] are counted by
+    /// the crate's render world systems, so they aren't available either, and
+    /// [`with_diagnostics`](Self::with_diagnostics) has no effect.
+    ///
+    /// [`PolylineStats`]: diagnostics::PolylineStats
+    pub fn without_default_pipeline(mut self) -> Self {
+        self.default_pipeline = false;
+        self
+    }
+
+    /// Adds the [`PolylineDiagnosticsPlugin`], reporting the number of polylines and segments
+    /// drawn and the bytes of vertex data uploaded each frame. Disabled by default, the same
+    /// numbers are always available in the [`PolylineStats`](diagnostics::PolylineStats)
+    /// resource.
+    pub fn with_diagnostics(mut self) -> Self {
+        self.diagnostics = true;
+        self
+    }
+
+    /// Drops vertices with a `NaN` or infinite coordinate before polylines are uploaded, logging
+    /// a warning the first time each polyline has any, see
+    /// [`Polyline::without_non_finite_vertices`].
+    /// Disabled by default, so that bad data isn't hidden where it's a bug, in which case such
+    /// vertices can break their segments or the whole draw.
+    ///
+    /// [`Polyline::without_non_finite_vertices`]: crate::polyline::Polyline::without_non_finite_vertices
+    pub fn with_vertex_sanitization(mut self) -> Self {
+        self.sanitize_vertices = true;
+        self
+    }
+
+    pub(crate) fn build_render(&self, app: &mut App) {
+        if self.default_pipeline {
+            self.build_pipeline(app);
+        } else {
+            material::register_material_types(app);
+        }
+        app.add_plugin(PolylineBoundsPlugin)
+            .add_plugin(ImmediateLinesPlugin);
+    }
+
+    fn build_pipeline(&self, app: &mut App) {
+        let mut shaders = app.world.get_resource_mut::<Assets<Shader>>().unwrap();
+        shaders.set_untracked(
+            SHADER_HANDLE,
+            Shader::from_wgsl(include_str!("../shaders/polyline.wgsl")),
+        );
+        #[cfg(feature = "shader_hot_reload")]
+        {
+            // Absolute paths replace the asset folder, so this watches the crate's sources
+            let asset_server = app.world.get_resource::<AssetServer>().unwrap();
+            let source = asset_server.load(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/src/shaders/polyline.wgsl"
+            ));
+            app.insert_resource(HotReloadedShader(source))
+                .add_system(hot_reload_shader);
+        }
+        // The material pipeline counts its specializations in the stats of the render world
+        app.add_plugin(PolylineStatsPlugin)
+            .add_plugin(PolylineRenderPlugin)
+            .add_plugin(PolylineMaterialPlugin);
+        if self.diagnostics {
+            app.add_plugin(PolylineDiagnosticsPlugin);
+        }
+        if self.sanitize_vertices {
+            if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+                render_app.insert_resource(SanitizeVertices);
+            }
+        }
+    }
+}
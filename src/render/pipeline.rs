@@ -0,0 +1,1706 @@
+use bevy::{
+    core::cast_slice,
+    ecs::system::{
+        lifetimeless::{Read, SQuery, SRes},
+        SystemParamItem,
+    },
+    pbr::{GlobalLightMeta, LightMeta, ViewClusterBindings, ViewShadowBindings},
+    prelude::*,
+    render::{
+        render_asset::RenderAssets,
+        render_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponentPlugin, UniformComponentPlugin,
+        },
+        render_phase::{EntityRenderCommand, RenderCommandResult, TrackedRenderPass},
+        render_resource::*,
+        renderer::{RenderDevice, RenderQueue},
+        texture::BevyDefault,
+        view::{ExtractedView, ViewUniform, ViewUniforms},
+        RenderApp, RenderStage,
+    },
+    utils::{HashMap, HashSet},
+};
+use std::ops::Range;
+
+use crate::{
+    diagnostics::{PolylineBufferUpdated, RenderPolylineStats},
+    material::{Arrowheads, GpuPolylineMaterial, Markers, PolylineMaterial, MAX_ROUND_RESOLUTION},
+    polyline::{
+        BakedPolyline, Polyline, Polyline2d, PolylineCamera, PolylineInstances, PolylineMode,
+        PolylineOpacity, PolylineReveal, PolylineWidth, SEGMENT_QUAD_VERTICES,
+    },
+    render::SanitizeVertices,
+    SHADER_HANDLE,
+};
+
+/// First vertex index of the start cap geometry, see `shaders/polyline.wgsl`.
+pub const START_CAP_VERTEX_OFFSET: u32 = 1024;
+/// First vertex index of the end cap geometry, see `shaders/polyline.wgsl`.
+pub const END_CAP_VERTEX_OFFSET: u32 = 2048;
+/// First vertex index of the join geometry, see `shaders/polyline.wgsl`.
+pub const JOIN_VERTEX_OFFSET: u32 = 3072;
+/// First vertex index of the start arrowhead geometry, see `shaders/polyline.wgsl`.
+pub const START_ARROWHEAD_VERTEX_OFFSET: u32 = 4096;
+/// First vertex index of the end arrowhead geometry, see `shaders/polyline.wgsl`.
+pub const END_ARROWHEAD_VERTEX_OFFSET: u32 = 5120;
+/// First vertex index of the markers at the start of segments, see `shaders/polyline.wgsl`.
+pub const START_MARKER_VERTEX_OFFSET: u32 = 6144;
+/// First vertex index of the markers at the end of segments, see `shaders/polyline.wgsl`.
+pub const END_MARKER_VERTEX_OFFSET: u32 = 7168;
+
+/// Number of vertices of the two triangles drawn for a segment, a square cap or a miter join.
+pub const QUAD_VERTEX_COUNT: u32 = SEGMENT_QUAD_VERTICES.len() as u32;
+
+// Each part of the line is drawn from its own range of vertex indices, the roundest caps and
+// joins must not spill into the range of the next part
+const _: () = assert!(
+    6 * MAX_ROUND_RESOLUTION <= END_CAP_VERTEX_OFFSET - START_CAP_VERTEX_OFFSET
+        && QUAD_VERTEX_COUNT <= START_CAP_VERTEX_OFFSET,
+    "the vertex index range of each part must fit its largest geometry"
+);
+
+/// Layout of a vertex in the buffer written by `Polyline::vertex_buffer_data()`, the format of
+/// each interleaved attribute in order, followed by the shader locations the attribute is read
+/// from for the start and the end of a segment. The previous position is only read for the start.
+const VERTEX_ATTRIBUTES: [(VertexFormat, &[u32]); 6] = [
+    // Position
+    (VertexFormat::Float32x3, &[0, 1]),
+    // Position of the previous vertex
+    (VertexFormat::Float32x3, &[11]),
+    // Linear color
+    (VertexFormat::Float32x4, &[2, 3]),
+    // Arc length
+    (VertexFormat::Float32, &[5, 6]),
+    // Width
+    (VertexFormat::Float32, &[7, 8]),
+    // Scalar
+    (VertexFormat::Float32, &[9, 10]),
+];
+
+/// Shader location of the position of the vertex following a segment, read to draw joins.
+///
+/// The position of the vertex preceding a segment is stored in every vertex instead, since
+/// reading it from the vertex before would take a padding vertex in front of every buffer, which
+/// would shift the data of compute passes writing vertex buffers.
+const NEXT_POSITION_LOCATION: u32 = 4;
+
+/// Number of `f32`s per vertex in the buffer written by `Polyline::vertex_buffer_data()`, must
+/// match `VERTEX_FLOATS` in `shaders/polyline.wgsl`.
+const VERTEX_FLOATS: usize = 13;
+
+/// Number of `u32`s per segment in the segment buffer of an indexed polyline, the size of
+/// `IndexedSegment` in `shaders/polyline.wgsl`.
+#[cfg(not(feature = "webgl"))]
+const SEGMENT_WORDS: usize = 4;
+
+/// Size of a vertex in the vertex buffer of a polyline, in bytes.
+pub const VERTEX_SIZE: u64 = vertex_size();
+
+const fn vertex_size() -> u64 {
+    let mut size = 0;
+    let mut i = 0;
+    while i < VERTEX_ATTRIBUTES.len() {
+        size += VERTEX_ATTRIBUTES[i].0.size();
+        i += 1;
+    }
+    size
+}
+
+// A mismatch between the data written and the layout read by the pipeline would draw garbage
+// without any error
+const _: () = assert!(
+    VERTEX_SIZE == (VERTEX_FLOATS * std::mem::size_of::<f32>()) as u64,
+    "the vertex stride must equal the sum of the attribute sizes"
+);
+
+pub struct PolylineRenderPlugin;
+impl Plugin for PolylineRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PolylineVertexUpdates>()
+            .add_plugin(UniformComponentPlugin::<PolylineUniform>::default())
+            .add_plugin(ExtractComponentPlugin::<Polyline2d>::default())
+            .add_plugin(ExtractComponentPlugin::<PolylineCamera>::default());
+        let render_app = app.sub_app_mut(RenderApp);
+        #[cfg(feature = "webgl")]
+        render_app.init_resource::<PolylineInstanceUniforms>();
+        render_app
+            .init_resource::<ExtractedPolylines>()
+            .init_resource::<RenderPolylines>()
+            .init_resource::<PolylinePipeline>()
+            .add_system_to_stage(RenderStage::Extract, extract_polyline_assets)
+            .add_system_to_stage(RenderStage::Extract, extract_polylines)
+            .add_system_to_stage(RenderStage::Prepare, prepare_polylines)
+            .add_system_to_stage(RenderStage::Queue, queue_polyline_bind_group)
+            .add_system_to_stage(RenderStage::Queue, queue_polyline_instances)
+            .add_system_to_stage(RenderStage::Queue, queue_polyline_view_bind_groups);
+    }
+}
+
+impl Polyline {
+    /// Interleaves position, previous position, linear color, arc length, width and scalar per
+    /// vertex, in the order of `VERTEX_ATTRIBUTES` which `PolylinePipeline::specialize()` builds
+    /// the vertex layout from.
+    fn vertex_buffer_data(&self) -> Vec<f32> {
+        let closed = self.is_closed();
+        let vertex_count = self.vertices.len();
+        let mut vertex_buffer_data = Vec::with_capacity((vertex_count + 2) * VERTEX_FLOATS);
+        // A closed polyline repeats its first vertex at the end so the last instance draws the
+        // closing segment. Every instance also reads the vertex following its segment to draw
+        // joins, so the buffer is padded with one more vertex: the second one for closed
+        // polylines, a copy of the last one otherwise.
+        let padding = if closed {
+            vec![0, 1]
+        } else {
+            vertex_count.checked_sub(1).into_iter().collect()
+        };
+        let indices = (0..vertex_count).chain(padding);
+        let mut arc_length = 0.0;
+        let mut previous: Option<Vec3> = None;
+        for (n, i) in indices.enumerate() {
+            let vertex = self.vertices[i];
+            // The vertices of a line list are only connected within each pair. Vertices starting
+            // an open line or a segment of a list have no previous vertex and repeat their own
+            // position instead.
+            let connected = self.mode == PolylineMode::LineStrip || n % 2 == 1;
+            let previous_vertex = match previous {
+                Some(previous) if connected => {
+                    arc_length += previous.distance(vertex);
+                    previous
+                }
+                None if closed => self.vertices[vertex_count - 1],
+                _ => vertex,
+            };
+            previous = Some(vertex);
+
+            self.push_vertex_data(&mut vertex_buffer_data, i, previous_vertex, arc_length);
+            debug_assert_eq!(vertex_buffer_data.len(), (n + 1) * VERTEX_FLOATS);
+        }
+        vertex_buffer_data
+    }
+
+    /// Writes the vertices of an indexed line list once each, in the layout of
+    /// `vertex_buffer_data()` with arc lengths of `0.0` and their own position as the previous
+    /// one, along with the segments, each as the indices of its two vertices followed by the arc
+    /// lengths at both of its ends, as the bits of `f32`s. Pairs of indices out of bounds are
+    /// skipped.
+    #[cfg(not(feature = "webgl"))]
+    fn indexed_buffer_data(&self) -> (Vec<f32>, Vec<u32>) {
+        let mut vertex_data = Vec::with_capacity(self.vertices.len() * VERTEX_FLOATS);
+        for i in 0..self.vertices.len() {
+            self.push_vertex_data(&mut vertex_data, i, self.vertices[i], 0.0);
+        }
+        let mut segment_data = Vec::new();
+        let mut arc_length = 0.0;
+        for [a, b] in self.segment_indices() {
+            let end = arc_length + self.vertices[a].distance(self.vertices[b]);
+            segment_data.extend([a as u32, b as u32, arc_length.to_bits(), end.to_bits()]);
+            arc_length = end;
+        }
+        debug_assert_eq!(segment_data.len() % SEGMENT_WORDS, 0);
+        (vertex_data, segment_data)
+    }
+
+    fn push_vertex_data(&self, data: &mut Vec<f32>, i: usize, previous: Vec3, arc_length: f32) {
+        let color = if self.colors.len() == self.vertices.len() {
+            self.colors[i].as_linear_rgba_f32()
+        } else {
+            [1.0; 4]
+        };
+        data.extend_from_slice(&self.vertices[i].to_array());
+        data.extend_from_slice(&previous.to_array());
+        data.extend_from_slice(&color);
+        data.push(arc_length);
+        data.push(self.width_at(i));
+        data.push(self.scalar_at(i));
+    }
+
+    /// Whether [`Polyline::deduplicated`] would remove any vertex.
+    fn has_duplicate_vertices(&self) -> bool {
+        if self.mode == PolylineMode::LineList {
+            return false;
+        }
+        let n = self.vertices.len();
+        (self.closed && n > 2 && self.vertices[0] == self.vertices[n - 1])
+            || self.vertices.windows(2).any(|pair| pair[0] == pair[1])
+    }
+
+    /// Whether any vertex has a `NaN` or infinite coordinate.
+    fn has_non_finite_vertices(&self) -> bool {
+        self.vertices.iter().any(|v| !v.is_finite())
+    }
+
+    /// Length the arc lengths of the vertex buffer are divided by for gradients, tapering and
+    /// reveals, see [`PolylineUniform::total_length`]. Non-finite vertices are left out as if the
+    /// line was sanitized, since they would make the length `NaN`.
+    fn drawn_length(&self) -> f32 {
+        if self.has_non_finite_vertices() {
+            self.without_non_finite_vertices().length()
+        } else {
+            self.length()
+        }
+    }
+}
+
+/// Render world storage of the [`GpuPolyline`]s, keyed by the handle of their [`Polyline`].
+pub type RenderPolylines = HashMap<Handle<Polyline>, GpuPolyline>;
+
+/// [`Polyline`]s that were created, modified or removed since the last frame. Polylines only
+/// changed through [`PolylineVertexUpdates`] come with the range of vertices that were updated.
+#[derive(Default)]
+pub struct ExtractedPolylines {
+    extracted: Vec<(Handle<Polyline>, Polyline, Option<Range<usize>>)>,
+    removed: Vec<Handle<Polyline>>,
+}
+
+/// Records the vertices of [`Polyline`]s updated in place, so that only the affected part of
+/// their vertex buffers is uploaded.
+///
+/// Modifying a polyline through `Assets::get_mut` uploads all of its vertex data again, since
+/// there is no telling which vertices changed. Editors moving a few vertices of a long line can
+/// write them with [`PolylineVertexUpdates::update_vertices`] instead. The upload still starts at
+/// the first updated vertex and runs to the end of the line, because the arc lengths of all the
+/// vertices after it depend on the edit, so edits near the end of a line are the cheapest.
+///
+/// The whole line is uploaded as usual if it was also modified some other way in the same frame,
+/// and for lines that are indexed, drawn from a [`Polyline::gpu_capacity`], have consecutive
+/// duplicate vertices or have non-finite vertices removed by sanitization.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_polyline::prelude::*;
+/// /// Drags the first vertex of every polyline up.
+/// fn drag_first_vertex(
+///     mut updates: ResMut<PolylineVertexUpdates>,
+///     mut polylines: ResMut<Assets<Polyline>>,
+///     query: Query<&Handle<Polyline>>,
+///     time: Res<Time>,
+/// ) {
+///     for handle in query.iter() {
+///         let height = time.seconds_since_startup() as f32;
+///         updates.update_vertices(&mut polylines, handle, 0, &[Vec3::Y * height]);
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct PolylineVertexUpdates {
+    updates: HashMap<Handle<Polyline>, VertexUpdate>,
+}
+
+/// Vertices updated in a polyline since the last extraction.
+struct VertexUpdate {
+    /// Union of the ranges of vertices written.
+    range: Range<usize>,
+    /// Number of updates, each of which sent an `AssetEvent::Modified`.
+    count: usize,
+}
+
+impl PolylineVertexUpdates {
+    /// Overwrites the vertices of the polyline starting at index `start` with `vertices`, and
+    /// records them as the part of its vertex buffer to upload. Returns `false` without changing
+    /// anything if the polyline doesn't exist.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the polyline has fewer than `start + vertices.len()` vertices.
+    pub fn update_vertices(
+        &mut self,
+        polylines: &mut Assets<Polyline>,
+        handle: &Handle<Polyline>,
+        start: usize,
+        vertices: &[Vec3],
+    ) -> bool {
+        let polyline = match polylines.get_mut(handle) {
+            Some(polyline) => polyline,
+            None => return false,
+        };
+        let range = start..start + vertices.len();
+        polyline.vertices[range.clone()].copy_from_slice(vertices);
+        let update = self
+            .updates
+            .entry(handle.clone_weak())
+            .or_insert_with(|| VertexUpdate {
+                range: range.clone(),
+                count: 0,
+            });
+        update.range = update.range.start.min(range.start)..update.range.end.max(range.end);
+        update.count += 1;
+        true
+    }
+}
+
+/// Extracts the [`Polyline`] assets that changed since the last frame.
+///
+/// GPU buffers belong to assets rather than entities: despawning an entity drops its handle, and
+/// once the last strong handle to a polyline is gone the asset is removed and its vertex buffer
+/// freed by [`prepare_polylines`]. Polylines inserted inline as components get a handle of their
+/// own, so they are freed the same way when their entity is despawned.
+pub fn extract_polyline_assets(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Polyline>>,
+    assets: Res<Assets<Polyline>>,
+    mut vertex_updates: ResMut<PolylineVertexUpdates>,
+) {
+    // Number of modifications of each changed polyline, `None` for the ones created, which are
+    // always uploaded whole
+    let mut changed_assets: HashMap<&Handle<Polyline>, Option<usize>> = HashMap::default();
+    let mut removed = Vec::new();
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } => {
+                changed_assets.insert(handle, None);
+            }
+            AssetEvent::Modified { handle } => {
+                if let Some(count) = changed_assets.entry(handle).or_insert(Some(0)) {
+                    *count += 1;
+                }
+            }
+            AssetEvent::Removed { handle } => {
+                changed_assets.remove(handle);
+                removed.push(handle.clone_weak());
+            }
+        }
+    }
+
+    let mut extracted = Vec::new();
+    for (handle, modified) in changed_assets.drain() {
+        if let Some(polyline) = assets.get(handle) {
+            // Partial uploads are only safe if every modification was a vertex update
+            let updated = vertex_updates
+                .updates
+                .remove(handle)
+                .filter(|update| modified == Some(update.count))
+                .map(|update| update.range);
+            extracted.push((handle.clone_weak(), polyline.clone(), updated));
+        }
+    }
+    vertex_updates.updates.clear();
+
+    commands.insert_resource(ExtractedPolylines { extracted, removed });
+}
+
+/// Largest vertex buffer created for a single polyline, in bytes.
+///
+/// This is the maximum buffer size every WebGPU implementation supports. Each vertex takes
+/// [`VERTEX_SIZE`], 52 bytes, so a polyline can have up to about 5.1 million vertices. Longer
+/// polylines are skipped with an error instead of failing on the GPU.
+pub const MAX_VERTEX_BUFFER_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Uploads the extracted [`Polyline`]s to the GPU.
+///
+/// The vertex buffer of a modified polyline is reused as long as the new vertex data fits in it,
+/// so lines updated every frame don't reallocate their buffer. Once a buffer is too small it is
+/// replaced by one with twice the needed capacity, to make room for lines that keep growing.
+/// Polylines only changed through [`PolylineVertexUpdates`] upload the data from their first
+/// updated vertex on.
+#[cfg_attr(feature = "webgl", allow(unused_variables))]
+pub fn prepare_polylines(
+    mut extracted_polylines: ResMut<ExtractedPolylines>,
+    mut render_polylines: ResMut<RenderPolylines>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    stats: Res<RenderPolylineStats>,
+    pipeline: Res<PolylinePipeline>,
+    sanitize_vertices: Option<Res<SanitizeVertices>>,
+    mut warned_non_finite: Local<HashSet<Handle<Polyline>>>,
+) {
+    let mut uploaded = 0;
+    for removed in extracted_polylines.removed.drain(..) {
+        render_polylines.remove(&removed);
+        warned_non_finite.remove(&removed);
+    }
+
+    for (handle, polyline, updated) in extracted_polylines.extracted.drain(..) {
+        if let Some(capacity) = polyline.gpu_capacity {
+            let gpu_polyline = prepare_gpu_driven_polyline(
+                &render_device,
+                &stats,
+                &handle,
+                render_polylines.remove(&handle),
+                &polyline,
+                capacity,
+            );
+            match gpu_polyline {
+                Some(gpu_polyline) => {
+                    render_polylines.insert(handle, gpu_polyline);
+                }
+                None => error!(
+                    "Polyline {:?} with a capacity of {} vertices doesn't fit in a vertex buffer \
+                    of at most {} bytes and won't be drawn",
+                    handle, capacity, MAX_VERTEX_BUFFER_SIZE
+                ),
+            }
+            continue;
+        }
+
+        let sanitized = sanitize_vertices.is_some() && polyline.has_non_finite_vertices();
+        let polyline = if sanitized {
+            // Lines updated every frame would log on every upload
+            if warned_non_finite.insert(handle.clone_weak()) {
+                warn!(
+                    "Polyline {:?} has vertices with non-finite coordinates, which are skipped, \
+                    this warning is only logged once per polyline",
+                    handle
+                );
+            }
+            polyline.without_non_finite_vertices()
+        } else {
+            polyline
+        };
+        // Buffers can't be empty, and a polyline without vertices draws nothing anyway. It isn't
+        // kept in the render world, so its entities are skipped until it gets vertices.
+        if polyline.vertices.is_empty() {
+            render_polylines.remove(&handle);
+            continue;
+        }
+        // WebGL2 has no storage buffers, indexed polylines are uploaded unindexed instead
+        #[cfg(not(feature = "webgl"))]
+        if polyline.is_indexed() && polyline.segment_indices().next().is_some() {
+            render_polylines.remove(&handle);
+            match prepare_indexed_polyline(&render_device, &stats, &pipeline, &handle, &polyline) {
+                Some((gpu_polyline, size)) => {
+                    uploaded += size;
+                    render_polylines.insert(handle, gpu_polyline);
+                }
+                None => error!(
+                    "Indexed polyline {:?} with {} vertices and {} indices doesn't fit in storage \
+                    buffers of at most {} bytes and won't be drawn, split it into several \
+                    polylines",
+                    handle,
+                    polyline.vertices.len(),
+                    polyline.indices.len(),
+                    MAX_INDEXED_BUFFER_SIZE
+                ),
+            }
+            continue;
+        }
+        let deduplicated = polyline.is_indexed() || polyline.has_duplicate_vertices();
+        let polyline = if deduplicated {
+            polyline.deduplicated()
+        } else {
+            polyline
+        };
+        let vertex_buffer_data = polyline.vertex_buffer_data();
+        let contents: &[u8] = cast_slice(&vertex_buffer_data);
+        let size = contents.len() as u64;
+
+        if size > MAX_VERTEX_BUFFER_SIZE {
+            error!(
+                "Polyline {:?} with {} vertices doesn't fit in a vertex buffer of at most {} \
+                bytes and won't be drawn, split it into several polylines",
+                handle,
+                polyline.vertices.len(),
+                MAX_VERTEX_BUFFER_SIZE
+            );
+            render_polylines.remove(&handle);
+            continue;
+        }
+
+        let previous = render_polylines.remove(&handle);
+        // Vertex updates keep the layout of the buffer, so the vertices before the first updated
+        // one are left as they are, unless vertices were skipped or merged before the upload
+        let updated = match (updated, &previous) {
+            (Some(updated), Some(previous))
+                if !sanitized
+                    && !deduplicated
+                    && !previous.gpu_driven
+                    && previous.indices.is_none()
+                    && previous.vertex_count as usize == polyline.vertices.len()
+                    && previous.mode == polyline.mode
+                    && previous.closed == polyline.is_closed() =>
+            {
+                Some(updated)
+            }
+            _ => None,
+        };
+        let (vertex_buffer, buffer_size) = match previous {
+            Some(previous) if previous.buffer_size >= size => {
+                // Arc lengths add up along the line, so every vertex from the first updated one
+                // on is uploaded again. The first vertex of a closed line also holds the position
+                // of the last one as its previous position.
+                let start = updated.as_ref().map_or(0, |updated| updated.start);
+                let mut ranges = vec![start * VERTEX_FLOATS..vertex_buffer_data.len()];
+                if polyline.is_closed()
+                    && start > 0
+                    && updated.map_or(false, |updated| updated.end >= polyline.vertices.len())
+                {
+                    ranges.push(0..VERTEX_FLOATS);
+                }
+                let mut byte_len = 0;
+                for range in ranges {
+                    render_queue.write_buffer(
+                        &previous.vertex_buffer,
+                        (range.start * std::mem::size_of::<f32>()) as u64,
+                        cast_slice(&vertex_buffer_data[range.clone()]),
+                    );
+                    byte_len += (range.len() * std::mem::size_of::<f32>()) as u64;
+                }
+                uploaded += byte_len;
+                stats.add_buffer_update(PolylineBufferUpdated {
+                    polyline: handle.clone_weak(),
+                    buffer_id: previous.vertex_buffer.id(),
+                    byte_len,
+                    created: false,
+                });
+                (previous.vertex_buffer, previous.buffer_size)
+            }
+            previous => {
+                // Only over-allocate lines that have been updated before
+                let buffer_size = if previous.is_some() {
+                    size.max(4).next_power_of_two().min(MAX_VERTEX_BUFFER_SIZE)
+                } else {
+                    size
+                };
+                let vertex_buffer = render_device.create_buffer(&BufferDescriptor {
+                    label: Some("Polyline Vertex Buffer"),
+                    size: buffer_size,
+                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST | BufferUsages::STORAGE,
+                    mapped_at_creation: false,
+                });
+                render_queue.write_buffer(&vertex_buffer, 0, contents);
+                uploaded += size;
+                stats.add_buffer_update(PolylineBufferUpdated {
+                    polyline: handle.clone_weak(),
+                    buffer_id: vertex_buffer.id(),
+                    byte_len: size,
+                    created: true,
+                });
+                (vertex_buffer, buffer_size)
+            }
+        };
+
+        render_polylines.insert(
+            handle,
+            GpuPolyline {
+                vertex_buffer,
+                buffer_size,
+                vertex_count: polyline.vertices.len() as u32,
+                mode: polyline.mode,
+                closed: polyline.is_closed(),
+                gpu_driven: false,
+                indices: None,
+            },
+        );
+    }
+
+    stats.add_uploaded(uploaded);
+    stats.set_buffers(
+        render_polylines
+            .values()
+            .map(|polyline| polyline.vertex_count as usize)
+            .sum(),
+        render_polylines
+            .values()
+            .map(|polyline| {
+                polyline.buffer_size
+                    + polyline
+                        .indices
+                        .as_ref()
+                        .map_or(0, |indices| indices.segment_buffer_size)
+            })
+            .sum(),
+    );
+}
+
+/// Largest storage buffer created for the vertices or segments of an indexed polyline, in bytes,
+/// which is the default limit of WebGPU on the size of a storage buffer binding.
+pub const MAX_INDEXED_BUFFER_SIZE: u64 = 128 * 1024 * 1024;
+
+/// Uploads the vertices and segments of an indexed line list to storage buffers, along with the
+/// bind group they are read from. Returns the polyline and the number of bytes uploaded, or
+/// `None` if either buffer would exceed [`MAX_INDEXED_BUFFER_SIZE`].
+///
+/// Unlike other polylines, the buffers are reallocated whenever the polyline changes.
+#[cfg(not(feature = "webgl"))]
+fn prepare_indexed_polyline(
+    render_device: &RenderDevice,
+    stats: &RenderPolylineStats,
+    pipeline: &PolylinePipeline,
+    handle: &Handle<Polyline>,
+    polyline: &Polyline,
+) -> Option<(GpuPolyline, u64)> {
+    let (vertex_data, segment_data) = polyline.indexed_buffer_data();
+    let vertex_contents: &[u8] = cast_slice(&vertex_data);
+    let segment_contents: &[u8] = cast_slice(&segment_data);
+    if vertex_contents.len() as u64 > MAX_INDEXED_BUFFER_SIZE
+        || segment_contents.len() as u64 > MAX_INDEXED_BUFFER_SIZE
+    {
+        return None;
+    }
+
+    let vertex_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("Polyline Indexed Vertex Buffer"),
+        contents: vertex_contents,
+        usage: BufferUsages::VERTEX | BufferUsages::COPY_DST | BufferUsages::STORAGE,
+    });
+    let segment_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("Polyline Segment Buffer"),
+        contents: segment_contents,
+        usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
+    });
+    for (buffer, contents) in [
+        (&vertex_buffer, vertex_contents),
+        (&segment_buffer, segment_contents),
+    ] {
+        stats.add_buffer_update(PolylineBufferUpdated {
+            polyline: handle.clone_weak(),
+            buffer_id: buffer.id(),
+            byte_len: contents.len() as u64,
+            created: true,
+        });
+    }
+    let bind_group =
+        pipeline.storage_bind_group(render_device, &vertex_buffer, Some(&segment_buffer), None);
+
+    let uploaded = (vertex_contents.len() + segment_contents.len()) as u64;
+    let gpu_polyline = GpuPolyline {
+        vertex_buffer,
+        buffer_size: vertex_contents.len() as u64,
+        vertex_count: polyline.vertices.len() as u32,
+        mode: PolylineMode::LineList,
+        closed: false,
+        gpu_driven: false,
+        indices: Some(GpuPolylineIndices {
+            segment_count: (segment_data.len() / SEGMENT_WORDS) as u32,
+            segment_buffer_size: segment_contents.len() as u64,
+            segment_buffer,
+            bind_group,
+        }),
+    };
+    Some((gpu_polyline, uploaded))
+}
+
+/// Allocates the vertex buffer of a polyline with a [`Polyline::gpu_capacity`], or keeps the
+/// `previous` one if it already has the right size. Returns `None` if the capacity exceeds
+/// [`MAX_VERTEX_BUFFER_SIZE`].
+fn prepare_gpu_driven_polyline(
+    render_device: &RenderDevice,
+    stats: &RenderPolylineStats,
+    handle: &Handle<Polyline>,
+    previous: Option<GpuPolyline>,
+    polyline: &Polyline,
+    capacity: u32,
+) -> Option<GpuPolyline> {
+    // Room for the padding of closed polylines, which is larger than that of open ones
+    let size = (capacity as u64 + 2) * VERTEX_SIZE;
+    if size > MAX_VERTEX_BUFFER_SIZE {
+        return None;
+    }
+    let vertex_buffer = match previous {
+        // A buffer that was uploaded to holds CPU data the compute pass doesn't expect
+        Some(previous) if previous.buffer_size == size && previous.gpu_driven => {
+            previous.vertex_buffer
+        }
+        _ => {
+            let vertex_buffer = render_device.create_buffer(&BufferDescriptor {
+                label: Some("Polyline GPU-Driven Vertex Buffer"),
+                size,
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST | BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            });
+            stats.add_buffer_update(PolylineBufferUpdated {
+                polyline: handle.clone_weak(),
+                buffer_id: vertex_buffer.id(),
+                byte_len: 0,
+                created: true,
+            });
+            vertex_buffer
+        }
+    };
+    Some(GpuPolyline {
+        vertex_buffer,
+        buffer_size: size,
+        vertex_count: capacity,
+        mode: polyline.mode,
+        closed: polyline.closed && polyline.mode == PolylineMode::LineStrip && capacity > 2,
+        gpu_driven: true,
+        indices: None,
+    })
+}
+
+#[derive(ShaderType, Component, Clone)]
+pub struct PolylineUniform {
+    #[align(16)]
+    pub transform: Mat4,
+    //pub inverse_transpose_model: Mat4,
+    /// Width from the entity's [`PolylineWidth`], or a negative value to use the material width.
+    pub width: f32,
+    /// Opacity from the entity's [`PolylineOpacity`], `1.0` without one.
+    pub opacity: f32,
+    /// Fraction of the length of the line drawn, from the entity's [`PolylineReveal`]. `1.0` or
+    /// more draws the whole line.
+    pub reveal: f32,
+    /// Total length of the polyline, which the arc lengths of its vertices are divided by for
+    /// gradients, tapering and reveals. Passing it here rather than storing the fraction in every
+    /// vertex keeps edits from changing vertices they don't touch.
+    pub total_length: f32,
+}
+
+impl PolylineUniform {
+    /// Width the line is drawn with, given the width of its material.
+    pub fn width(&self, material_width: f32) -> f32 {
+        if self.width >= 0.0 {
+            self.width
+        } else {
+            material_width
+        }
+    }
+}
+
+/// The GPU-representation of a [`Polyline`]
+///
+/// Render world systems can look up the vertex buffer of a polyline by its handle in
+/// [`RenderPolylines`], to write positions from their own compute pass instead of uploading them
+/// from the CPU. The buffer is created with `BufferUsages::STORAGE`, so it can be bound as
+/// `array<f32>` storage, and holds [`VERTEX_SIZE`] bytes per vertex: position, position of the
+/// previous vertex, linear color, arc length, width and scalar. A closed polyline repeats its
+/// first two vertices at the end, an open one its last vertex. The first vertex of an open line,
+/// and of every segment of a line list, repeats its own position as the previous one. The
+/// previous positions and arc lengths have to stay consistent with the positions for joins and
+/// dashes. Gradients, tapering and reveals divide the arc lengths by the length of the
+/// polyline's `vertices` on the CPU, see [`PolylineUniform::total_length`].
+///
+/// Polylines written every frame should set [`Polyline::gpu_capacity`], so the crate never
+/// uploads to their buffer. Others are uploaded from the CPU whenever they change, which can
+/// overwrite what the pass wrote. Either way, run the pass after [`prepare_polylines`] in
+/// `RenderStage::Prepare`, and look up the buffer every frame since it is reallocated when the
+/// polyline grows, see [`GpuPolyline::vertex_buffer_id`].
+///
+/// An indexed [`PolylineMode::LineList`] holds each of its vertices once instead, with arc
+/// lengths of `0.0`, and is drawn from its [`GpuPolylineIndices`].
+#[derive(Debug, Clone)]
+pub struct GpuPolyline {
+    pub vertex_buffer: Buffer,
+    /// Size of `vertex_buffer` in bytes, which can be larger than the data it currently holds.
+    pub buffer_size: u64,
+    pub vertex_count: u32,
+    pub mode: PolylineMode,
+    pub closed: bool,
+    /// Whether `vertex_buffer` was allocated for a [`Polyline::gpu_capacity`], and is never
+    /// written by the crate.
+    pub gpu_driven: bool,
+    /// Segments of an indexed polyline, see [`Polyline::indices`]. Always `None` with the
+    /// `webgl` feature, since WebGL2 has no storage buffers.
+    pub indices: Option<GpuPolylineIndices>,
+}
+
+/// Segments of an indexed [`PolylineMode::LineList`], which is drawn from storage buffers
+/// instead of vertex attributes, so that vertices shared by several segments are only uploaded
+/// once. The vertex shader reads the segment of each instance from `segment_buffer`, and its
+/// two vertices from the vertex buffer of the [`GpuPolyline`], both bound by `bind_group`.
+#[derive(Debug, Clone)]
+pub struct GpuPolylineIndices {
+    /// The indices of the two vertices of every segment, followed by its arc lengths at both
+    /// ends, encoded as `f32` bits, for four `u32`s per segment.
+    pub segment_buffer: Buffer,
+    /// Size of `segment_buffer` in bytes.
+    pub segment_buffer_size: u64,
+    pub segment_count: u32,
+    /// Bind group of the vertex and segment buffers, at `group(3)` of the pipeline.
+    pub bind_group: BindGroup,
+}
+
+impl GpuPolyline {
+    /// Id of the vertex buffer, which changes whenever the buffer is reallocated.
+    pub fn vertex_buffer_id(&self) -> BufferId {
+        self.vertex_buffer.id()
+    }
+
+    /// Number of segments drawn, i.e. the number of instances of the draw call.
+    pub fn segment_count(&self) -> u32 {
+        if let Some(indices) = &self.indices {
+            return indices.segment_count;
+        }
+        match self.mode {
+            PolylineMode::LineStrip if self.closed => self.vertex_count,
+            PolylineMode::LineStrip => self.vertex_count.max(1) - 1,
+            PolylineMode::LineList => self.vertex_count / 2,
+        }
+    }
+
+    /// Number of joins between consecutive segments, which are drawn at the end of the first
+    /// `join_count()` segments.
+    pub fn join_count(&self) -> u32 {
+        match self.mode {
+            PolylineMode::LineStrip if self.closed => self.segment_count(),
+            PolylineMode::LineStrip => self.segment_count().max(1) - 1,
+            PolylineMode::LineList => 0,
+        }
+    }
+}
+
+/// Transforms of an entity's [`PolylineInstances`], relative to the entity's own transform.
+///
+/// All copies are drawn by the same draw calls as a single polyline would be, with one instance
+/// per segment and copy: the vertex shader reads the vertices of the segment from storage
+/// buffers rather than vertex attributes, along with the transform of the copy, see
+/// [`PreparedPolylineInstances`]. WebGL2 has no storage buffers, so with the `webgl` feature
+/// every copy is drawn separately with a [`PolylineUniform`] of its own instead.
+#[derive(Component)]
+pub struct ExtractedPolylineInstances(pub Vec<Mat4>);
+
+pub fn extract_polylines(
+    mut commands: Commands,
+    mut previous_len: Local<usize>,
+    mut lengths: Local<HashMap<Handle<Polyline>, f32>>,
+    mut events: EventReader<AssetEvent<Polyline>>,
+    polylines: Res<Assets<Polyline>>,
+    query: Query<(
+        Entity,
+        &ComputedVisibility,
+        &GlobalTransform,
+        &Handle<Polyline>,
+        Option<&PolylineInstances>,
+        Option<&PolylineWidth>,
+        Option<&PolylineOpacity>,
+        Option<&PolylineReveal>,
+        Option<&BakedPolyline>,
+    )>,
+) {
+    // Lengths are measured again only when their polyline changes
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle }
+            | AssetEvent::Modified { handle }
+            | AssetEvent::Removed { handle } => {
+                lengths.remove(handle);
+            }
+        }
+    }
+
+    let mut values = Vec::with_capacity(*previous_len);
+    let mut instanced = Vec::new();
+    for (
+        entity,
+        computed_visibility,
+        transform,
+        handle,
+        instances,
+        width,
+        opacity,
+        reveal,
+        baked,
+    ) in query.iter()
+    {
+        if !computed_visibility.is_visible {
+            continue;
+        }
+        // Baked polylines already have their transform applied to their vertices
+        let (handle, transform) = match baked {
+            Some(baked) => (&baked.0, Mat4::IDENTITY),
+            None => (handle, transform.compute_matrix()),
+        };
+        let width = width.map_or(-1.0, |width| width.0.max(0.0));
+        let opacity = opacity.map_or(1.0, |opacity| opacity.0.clamp(0.0, 1.0));
+        let reveal = reveal.map_or(1.0, |reveal| reveal.progress.max(0.0));
+        let total_length = match lengths.get(handle) {
+            Some(length) => *length,
+            None => {
+                let length = polylines.get(handle).map_or(0.0, Polyline::drawn_length);
+                lengths.insert(handle.clone_weak(), length);
+                length
+            }
+        };
+        values.push((
+            entity,
+            (
+                handle.clone_weak(),
+                PolylineUniform {
+                    transform,
+                    //inverse_transpose_model: transform.inverse().transpose(),
+                    width,
+                    opacity,
+                    reveal,
+                    total_length,
+                },
+            ),
+        ));
+
+        if let Some(instances) = instances {
+            let transforms = instances.0.iter().map(Transform::compute_matrix).collect();
+            instanced.push((entity, (ExtractedPolylineInstances(transforms),)));
+        }
+    }
+    *previous_len = values.len();
+    commands.insert_or_spawn_batch(values);
+    commands.insert_or_spawn_batch(instanced);
+}
+
+/// Bindings an entity's [`ExtractedPolylineInstances`] are drawn with, created every frame by
+/// [`queue_polyline_instances`].
+#[derive(Component)]
+pub struct PreparedPolylineInstances {
+    /// Bind group of the vertices of the polyline, its segments if it is indexed and the
+    /// transforms of the copies, at `group(3)` of the pipeline. With the `webgl` feature, the
+    /// bind group of the uniforms of the copies at `group(1)` instead.
+    pub bind_group: BindGroup,
+    /// Number of copies drawn.
+    #[cfg(not(feature = "webgl"))]
+    pub count: u32,
+    /// Dynamic offset of the uniform of every copy in [`PolylineInstanceUniforms`].
+    #[cfg(feature = "webgl")]
+    pub uniform_offsets: Vec<u32>,
+}
+
+/// Uploads the transforms of every entity's [`ExtractedPolylineInstances`] to a storage buffer,
+/// bound along with the vertices of its polyline. Entities without any instance draw nothing and
+/// get no bindings.
+#[cfg(not(feature = "webgl"))]
+pub fn queue_polyline_instances(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    pipeline: Res<PolylinePipeline>,
+    render_polylines: Res<RenderPolylines>,
+    query: Query<(Entity, &Handle<Polyline>, &ExtractedPolylineInstances)>,
+) {
+    for (entity, handle, instances) in query.iter() {
+        let gpu_polyline = match render_polylines.get(handle) {
+            Some(gpu_polyline) if !instances.0.is_empty() => gpu_polyline,
+            _ => continue,
+        };
+        let transforms: Vec<[f32; 16]> = instances.0.iter().map(Mat4::to_cols_array).collect();
+        let instance_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("Polyline Instance Buffer"),
+            contents: cast_slice(&transforms),
+            usage: BufferUsages::STORAGE,
+        });
+        let bind_group = pipeline.storage_bind_group(
+            &render_device,
+            &gpu_polyline.vertex_buffer,
+            gpu_polyline
+                .indices
+                .as_ref()
+                .map(|indices| &indices.segment_buffer),
+            Some(&instance_buffer),
+        );
+        commands.entity(entity).insert(PreparedPolylineInstances {
+            bind_group,
+            count: instances.0.len() as u32,
+        });
+    }
+}
+
+/// Uniforms of the copies of instanced polylines, which are drawn one at a time with the `webgl`
+/// feature.
+#[cfg(feature = "webgl")]
+#[derive(Default)]
+pub struct PolylineInstanceUniforms {
+    pub uniforms: DynamicUniformBuffer<PolylineUniform>,
+}
+
+/// Writes the [`PolylineUniform`] of every copy of the entities with
+/// [`ExtractedPolylineInstances`] to [`PolylineInstanceUniforms`], along with the bind group they
+/// are drawn with.
+#[cfg(feature = "webgl")]
+pub fn queue_polyline_instances(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    pipeline: Res<PolylinePipeline>,
+    mut instance_uniforms: ResMut<PolylineInstanceUniforms>,
+    query: Query<(Entity, &PolylineUniform, &ExtractedPolylineInstances)>,
+) {
+    instance_uniforms.uniforms.clear();
+    let mut prepared = Vec::new();
+    for (entity, uniform, instances) in query.iter() {
+        let uniform_offsets: Vec<u32> = instances
+            .0
+            .iter()
+            .map(|instance| {
+                instance_uniforms.uniforms.push(PolylineUniform {
+                    transform: uniform.transform * *instance,
+                    ..uniform.clone()
+                })
+            })
+            .collect();
+        if !uniform_offsets.is_empty() {
+            prepared.push((entity, uniform_offsets));
+        }
+    }
+    instance_uniforms
+        .uniforms
+        .write_buffer(&render_device, &render_queue);
+
+    if let Some(binding) = instance_uniforms.uniforms.binding() {
+        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: binding,
+            }],
+            label: Some("polyline_instance_bind_group"),
+            layout: &pipeline.polyline_layout,
+        });
+        for (entity, uniform_offsets) in prepared {
+            commands.entity(entity).insert(PreparedPolylineInstances {
+                bind_group: bind_group.clone(),
+                uniform_offsets,
+            });
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PolylinePipeline {
+    pub view_layout: BindGroupLayout,
+    pub polyline_layout: BindGroupLayout,
+    /// Layout of the storage buffers indexed and instanced polylines are drawn from: their
+    /// vertices, the segments of indexed polylines, see [`GpuPolylineIndices`], and the
+    /// transforms of instances, see [`ExtractedPolylineInstances`].
+    #[cfg(not(feature = "webgl"))]
+    pub storage_layout: BindGroupLayout,
+    /// Bound in place of the segments of polylines that aren't indexed.
+    #[cfg(not(feature = "webgl"))]
+    placeholder_segments: Buffer,
+    /// Bound in place of the transforms of polylines that aren't instanced.
+    #[cfg(not(feature = "webgl"))]
+    placeholder_instances: Buffer,
+}
+
+#[cfg(not(feature = "webgl"))]
+impl PolylinePipeline {
+    /// Binds the `vertices` of a polyline with its `segments` if it is indexed and the
+    /// transforms of its `instances` if it is instanced, in the layout of `storage_layout`.
+    pub fn storage_bind_group(
+        &self,
+        render_device: &RenderDevice,
+        vertices: &Buffer,
+        segments: Option<&Buffer>,
+        instances: Option<&Buffer>,
+    ) -> BindGroup {
+        render_device.create_bind_group(&BindGroupDescriptor {
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: vertices.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: segments
+                        .unwrap_or(&self.placeholder_segments)
+                        .as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: instances
+                        .unwrap_or(&self.placeholder_instances)
+                        .as_entire_binding(),
+                },
+            ],
+            label: Some("polyline_storage_bind_group"),
+            layout: &self.storage_layout,
+        })
+    }
+}
+
+impl FromWorld for PolylinePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.get_resource::<RenderDevice>().unwrap();
+        let view_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                // View
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: BufferSize::new(ViewUniform::min_size().into()),
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("polyline_view_layout"),
+        });
+
+        let polyline_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: BufferSize::new(PolylineUniform::min_size().into()),
+                },
+                count: None,
+            }],
+            label: Some("polyline_layout"),
+        });
+
+        #[cfg(not(feature = "webgl"))]
+        let storage_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                // Vertices
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(VERTEX_SIZE),
+                    },
+                    count: None,
+                },
+                // Segments
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(
+                            (SEGMENT_WORDS * std::mem::size_of::<u32>()) as u64,
+                        ),
+                    },
+                    count: None,
+                },
+                // Instance transforms
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(std::mem::size_of::<Mat4>() as u64),
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("polyline_storage_layout"),
+        });
+        #[cfg(not(feature = "webgl"))]
+        let placeholder_segments = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("Polyline Placeholder Segment Buffer"),
+            contents: &[0; SEGMENT_WORDS * std::mem::size_of::<u32>()],
+            usage: BufferUsages::STORAGE,
+        });
+        #[cfg(not(feature = "webgl"))]
+        let placeholder_instances = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("Polyline Placeholder Instance Buffer"),
+            contents: cast_slice(&Mat4::IDENTITY.to_cols_array()),
+            usage: BufferUsages::STORAGE,
+        });
+        PolylinePipeline {
+            view_layout,
+            polyline_layout,
+            #[cfg(not(feature = "webgl"))]
+            storage_layout,
+            #[cfg(not(feature = "webgl"))]
+            placeholder_segments,
+            #[cfg(not(feature = "webgl"))]
+            placeholder_instances,
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for PolylinePipeline {
+    type Key = PolylinePipelineKey;
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        // Each instance reads two consecutive vertices of the buffer, as well as the position of
+        // the vertex after them, and the previous position stored in the first one
+        let mut vertex_attributes = Vec::new();
+        let mut offset = 0;
+        for &(format, locations) in VERTEX_ATTRIBUTES.iter() {
+            for (i, &shader_location) in locations.iter().enumerate() {
+                vertex_attributes.push(VertexAttribute {
+                    format,
+                    offset: offset + i as u64 * VERTEX_SIZE,
+                    shader_location,
+                });
+            }
+            offset += format.size();
+        }
+        vertex_attributes.push(VertexAttribute {
+            format: VERTEX_ATTRIBUTES[0].0,
+            offset: 2 * VERTEX_SIZE,
+            shader_location: NEXT_POSITION_LOCATION,
+        });
+        // In line list mode each instance skips ahead two vertices instead of one, so
+        // consecutive pairs are never connected
+        let array_stride = if key.contains(PolylinePipelineKey::LINE_LIST) {
+            2 * VERTEX_SIZE
+        } else {
+            VERTEX_SIZE
+        };
+        let shader_defs = Vec::new();
+        let (label, blend, mut depth_write_enabled);
+
+        if key.contains(PolylinePipelineKey::ADDITIVE) {
+            label = "additive_polyline_pipeline".into();
+            blend = Some(BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::SrcAlpha,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+                // Leave the destination alpha untouched
+                alpha: BlendComponent {
+                    src_factor: BlendFactor::Zero,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+            });
+            depth_write_enabled = false;
+        } else if key.contains(PolylinePipelineKey::TRANSPARENT_MAIN_PASS) {
+            label = "transparent_polyline_pipeline".into();
+            blend = Some(BlendState::ALPHA_BLENDING);
+            // For the transparent pass, fragments that are closer will be alpha blended
+            // but their depth is not written to the depth buffer
+            depth_write_enabled = false;
+        } else {
+            label = "opaque_polyline_pipeline".into();
+            blend = Some(BlendState::REPLACE);
+            // For the opaque and alpha mask passes, fragments that are closer will replace
+            // the current fragment value in the output and the depth is written to the
+            // depth buffer
+            depth_write_enabled = true;
+        }
+        // Lines layered with a z order are drawn over everything, and shouldn't occlude what is
+        // drawn after them either
+        let depth_compare = if key.contains(PolylinePipelineKey::ON_TOP) {
+            depth_write_enabled = false;
+            CompareFunction::Always
+        } else {
+            CompareFunction::Greater
+        };
+
+        let mut descriptor = RenderPipelineDescriptor {
+            vertex: VertexState {
+                shader: SHADER_HANDLE.typed::<Shader>(),
+                entry_point: "vertex".into(),
+                shader_defs: shader_defs.clone(),
+                buffers: vec![VertexBufferLayout {
+                    array_stride,
+                    step_mode: VertexStepMode::Instance,
+                    attributes: vertex_attributes,
+                }],
+            },
+            fragment: Some(FragmentState {
+                shader: SHADER_HANDLE.typed::<Shader>(),
+                shader_defs,
+                entry_point: "fragment".into(),
+                // The main pass has no HDR target for emissive lines to write to, they are
+                // drawn to the same format as every other mesh
+                targets: vec![ColorTargetState {
+                    format: TextureFormat::bevy_default(),
+                    blend,
+                    write_mask: ColorWrites::ALL,
+                }],
+            }),
+            layout: None, // This is set in `PolylineMaterialPipeline::specialize()`
+            primitive: PrimitiveState {
+                front_face: FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: PolygonMode::Fill,
+                conservative: false,
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled,
+                depth_compare,
+                stencil: StencilState {
+                    front: StencilFaceState::IGNORE,
+                    back: StencilFaceState::IGNORE,
+                    read_mask: 0,
+                    write_mask: 0,
+                },
+                bias: DepthBiasState {
+                    constant: 0,
+                    slope_scale: 0.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: MultisampleState {
+                count: key.msaa_samples(),
+                mask: !0,
+                alpha_to_coverage_enabled: false, //TODO: Do we need this for blending faded lines?
+            },
+            label: Some(label),
+        };
+        // The 2D pass has no depth buffer
+        if key.contains(PolylinePipelineKey::VIEW_2D) {
+            descriptor.depth_stencil = None;
+        }
+        // Indexed and instanced polylines read their vertices from storage buffers by instance
+        // index
+        if key.intersects(PolylinePipelineKey::INDEXED | PolylinePipelineKey::INSTANCED) {
+            descriptor.vertex.buffers.clear();
+            let shader_defs = &mut descriptor.vertex.shader_defs;
+            shader_defs.push("POLYLINE_STORAGE_VERTICES".to_string());
+            if key.contains(PolylinePipelineKey::INDEXED) {
+                shader_defs.push("POLYLINE_INDEXED".to_string());
+            }
+            if key.contains(PolylinePipelineKey::INSTANCED) {
+                shader_defs.push("POLYLINE_INSTANCED".to_string());
+            }
+            if key.contains(PolylinePipelineKey::LINE_LIST) {
+                shader_defs.push("POLYLINE_LINE_LIST".to_string());
+            }
+        }
+        descriptor
+    }
+}
+
+bitflags::bitflags! {
+    #[repr(transparent)]
+    // NOTE: Apparently quadro drivers support up to 64x MSAA.
+    /// MSAA uses the highest 6 bits for the MSAA sample count - 1 to support up to 64x MSAA.
+    pub struct PolylinePipelineKey: u32 {
+        const NONE = 0;
+        const PERSPECTIVE = (1 << 0);
+        const TRANSPARENT_MAIN_PASS = (1 << 1);
+        const LINE_LIST = (1 << 2);
+        const ADDITIVE = (1 << 3);
+        const ANTIALIAS = (1 << 4);
+        const VIEW_2D = (1 << 5);
+        const ON_TOP = (1 << 6);
+        const RIBBON = (1 << 7);
+        const WORLD_WIDTH = (1 << 8);
+        const INDEXED = (1 << 9);
+        const INSTANCED = (1 << 10);
+        const MSAA_RESERVED_BITS = PolylinePipelineKey::MSAA_MASK_BITS << PolylinePipelineKey::MSAA_SHIFT_BITS;
+    }
+}
+
+impl PolylinePipelineKey {
+    const MSAA_MASK_BITS: u32 = 0b111111;
+    const MSAA_SHIFT_BITS: u32 = 32 - 6;
+
+    pub fn from_msaa_samples(msaa_samples: u32) -> Self {
+        let msaa_bits = ((msaa_samples - 1) & Self::MSAA_MASK_BITS) << Self::MSAA_SHIFT_BITS;
+        PolylinePipelineKey::from_bits(msaa_bits).unwrap()
+    }
+
+    pub fn msaa_samples(&self) -> u32 {
+        ((self.bits >> Self::MSAA_SHIFT_BITS) & Self::MSAA_MASK_BITS) + 1
+    }
+}
+
+pub struct PolylineBindGroup {
+    pub value: BindGroup,
+}
+
+pub fn queue_polyline_bind_group(
+    mut commands: Commands,
+    polyline_pipeline: Res<PolylinePipeline>,
+    render_device: Res<RenderDevice>,
+    polyline_uniforms: Res<ComponentUniforms<PolylineUniform>>,
+) {
+    if let Some(binding) = polyline_uniforms.uniforms().binding() {
+        commands.insert_resource(PolylineBindGroup {
+            value: render_device.create_bind_group(&BindGroupDescriptor {
+                entries: &[BindGroupEntry {
+                    binding: 0,
+                    resource: binding,
+                }],
+                label: Some("polyline_bind_group"),
+                layout: &polyline_pipeline.polyline_layout,
+            }),
+        });
+    }
+}
+
+#[derive(Component)]
+pub struct PolylineViewBindGroup {
+    pub value: BindGroup,
+}
+
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn queue_polyline_view_bind_groups(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    polyline_pipeline: Res<PolylinePipeline>,
+    light_meta: Res<LightMeta>,
+    global_light_meta: Res<GlobalLightMeta>,
+    view_uniforms: Res<ViewUniforms>,
+    // 2D views have no shadow or cluster bindings, which aren't bound yet anyway
+    views: Query<
+        (
+            Entity,
+            Option<&ViewShadowBindings>,
+            Option<&ViewClusterBindings>,
+        ),
+        With<ExtractedView>,
+    >,
+) {
+    if let (Some(view_binding), Some(_light_binding), Some(_point_light_binding)) = (
+        view_uniforms.uniforms.binding(),
+        light_meta.view_gpu_lights.binding(),
+        global_light_meta.gpu_point_lights.binding(),
+    ) {
+        for (entity, _view_shadow_bindings, _view_cluster_bindings) in views.iter() {
+            let view_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: view_binding.clone(),
+                    },
+                    /* Can add these bindings in the future if needed
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: light_binding.clone(),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: BindingResource::TextureView(
+                            &view_shadow_bindings.point_light_depth_texture_view,
+                        ),
+                    },
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: BindingResource::Sampler(&shadow_pipeline.point_light_sampler),
+                    },
+                    BindGroupEntry {
+                        binding: 4,
+                        resource: BindingResource::TextureView(
+                            &view_shadow_bindings.directional_light_depth_texture_view,
+                        ),
+                    },
+                    BindGroupEntry {
+                        binding: 5,
+                        resource: BindingResource::Sampler(
+                            &shadow_pipeline.directional_light_sampler,
+                        ),
+                    },
+                    BindGroupEntry {
+                        binding: 6,
+                        resource: point_light_binding.clone(),
+                    },
+                    BindGroupEntry {
+                        binding: 7,
+                        resource: view_cluster_bindings
+                            .cluster_light_index_lists
+                            .binding()
+                            .unwrap(),
+                    },
+                    BindGroupEntry {
+                        binding: 8,
+                        resource: view_cluster_bindings
+                            .cluster_offsets_and_counts
+                            .binding()
+                            .unwrap(),
+                    },
+                    */
+                ],
+                label: Some("polyline_view_bind_group"),
+                layout: &polyline_pipeline.view_layout,
+            });
+
+            commands.entity(entity).insert(PolylineViewBindGroup {
+                value: view_bind_group,
+            });
+        }
+    }
+}
+
+pub struct SetPolylineBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetPolylineBindGroup<I> {
+    type Param = (
+        SRes<PolylineBindGroup>,
+        SQuery<Read<DynamicUniformIndex<PolylineUniform>>>,
+    );
+    #[inline]
+    fn render<'w>(
+        _view: Entity,
+        item: Entity,
+        (polyline_bind_group, polyline_query): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let polyline_index = polyline_query.get(item).unwrap();
+        pass.set_bind_group(
+            I,
+            &polyline_bind_group.into_inner().value,
+            &[polyline_index.index()],
+        );
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawPolyline;
+impl EntityRenderCommand for DrawPolyline {
+    #[allow(clippy::type_complexity)]
+    type Param = (
+        SRes<RenderPolylines>,
+        SRes<RenderAssets<PolylineMaterial>>,
+        SQuery<(
+            Read<Handle<Polyline>>,
+            Read<Handle<PolylineMaterial>>,
+            Option<Read<PreparedPolylineInstances>>,
+        )>,
+        SRes<RenderPolylineStats>,
+    );
+    #[inline]
+    fn render<'w>(
+        _view: Entity,
+        item: Entity,
+        (polylines, materials, pl_query, stats): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let stats = stats.into_inner();
+        let (pl_handle, material_handle, instances) = pl_query.get(item).unwrap();
+        if let (Some(gpu_polyline), Some(material)) = (
+            polylines.into_inner().get(pl_handle),
+            materials.into_inner().get(material_handle),
+        ) {
+            match instances {
+                // Every draw call covers all copies, which read their vertices and transforms
+                // from the storage buffers of the bind group
+                #[cfg(not(feature = "webgl"))]
+                Some(instances) => {
+                    pass.set_bind_group(3, &instances.bind_group, &[]);
+                    draw_polyline(pass, gpu_polyline, material, stats, instances.count);
+                }
+                // Copies only differ by their polyline uniform, so the uniforms of the copies
+                // are bound in place of the one set by `SetPolylineBindGroup<1>`
+                #[cfg(feature = "webgl")]
+                Some(instances) => {
+                    pass.set_vertex_buffer(0, gpu_polyline.vertex_buffer.slice(..));
+                    for &offset in &instances.uniform_offsets {
+                        pass.set_bind_group(1, &instances.bind_group, &[offset]);
+                        draw_polyline(pass, gpu_polyline, material, stats, 1);
+                    }
+                }
+                None => {
+                    match &gpu_polyline.indices {
+                        Some(indices) => pass.set_bind_group(3, &indices.bind_group, &[]),
+                        None => pass.set_vertex_buffer(0, gpu_polyline.vertex_buffer.slice(..)),
+                    }
+                    draw_polyline(pass, gpu_polyline, material, stats, 1);
+                }
+            }
+            RenderCommandResult::Success
+        } else {
+            RenderCommandResult::Failure
+        }
+    }
+}
+
+/// Issues the draw calls for the segments, caps, joins, arrowheads and markers of `copies`
+/// copies of a polyline whose vertex buffer, or storage bind group for an indexed or instanced
+/// polyline, is already bound.
+fn draw_polyline<'w>(
+    pass: &mut TrackedRenderPass<'w>,
+    gpu_polyline: &'w GpuPolyline,
+    material: &GpuPolylineMaterial,
+    stats: &RenderPolylineStats,
+    copies: u32,
+) {
+    let segment_count = gpu_polyline.segment_count();
+    stats.add_drawn(copies, segment_count);
+    let draw = |pass: &mut TrackedRenderPass<'w>, vertices, instances| {
+        draw_instances(pass, stats, gpu_polyline, vertices, instances, copies)
+    };
+    draw(pass, 0..QUAD_VERTEX_COUNT, 0..segment_count);
+
+    // Caps are drawn as additional geometry on the first and last segment, the shader
+    // selects the cap shape from the vertex index range
+    let cap_vertices = material.cap.vertex_count(material.round_resolution);
+    if segment_count > 0 && !gpu_polyline.closed {
+        let (start_instances, end_instances) = match gpu_polyline.mode {
+            PolylineMode::LineStrip => (0..1, segment_count - 1..segment_count),
+            PolylineMode::LineList => (0..segment_count, 0..segment_count),
+        };
+        if cap_vertices > 0 {
+            draw(
+                pass,
+                START_CAP_VERTEX_OFFSET..START_CAP_VERTEX_OFFSET + cap_vertices,
+                start_instances.clone(),
+            );
+            draw(
+                pass,
+                END_CAP_VERTEX_OFFSET..END_CAP_VERTEX_OFFSET + cap_vertices,
+                end_instances.clone(),
+            );
+        }
+
+        // Arrowheads are drawn the same way as caps, on top of them
+        if material.arrowheads.has_start() {
+            draw(
+                pass,
+                START_ARROWHEAD_VERTEX_OFFSET
+                    ..START_ARROWHEAD_VERTEX_OFFSET + Arrowheads::VERTEX_COUNT,
+                start_instances,
+            );
+        }
+        if material.arrowheads.has_end() {
+            draw(
+                pass,
+                END_ARROWHEAD_VERTEX_OFFSET..END_ARROWHEAD_VERTEX_OFFSET + Arrowheads::VERTEX_COUNT,
+                end_instances,
+            );
+        }
+    }
+
+    let join_vertices = material.join.vertex_count(material.round_resolution);
+    let join_count = gpu_polyline.join_count();
+    if join_vertices > 0 && join_count > 0 {
+        draw(
+            pass,
+            JOIN_VERTEX_OFFSET..JOIN_VERTEX_OFFSET + join_vertices,
+            0..join_count,
+        );
+    }
+
+    // Markers are drawn last, on top of the rest of the line, at the first vertex of segments
+    // and the last vertex of the last segment
+    let marker_vertices = material.markers.vertex_count(material.round_resolution);
+    if marker_vertices > 0 && segment_count > 0 {
+        let (start_instances, end_instances) = match gpu_polyline.mode {
+            PolylineMode::LineList => (0..segment_count, 0..segment_count),
+            PolylineMode::LineStrip if gpu_polyline.closed => match material.markers {
+                Markers::AllVertices => (0..segment_count, 0..0),
+                _ => (0..0, 0..0),
+            },
+            PolylineMode::LineStrip => match material.markers {
+                Markers::AllVertices => (0..segment_count, segment_count - 1..segment_count),
+                _ => (0..1, segment_count - 1..segment_count),
+            },
+        };
+        for (offset, instances) in [
+            (START_MARKER_VERTEX_OFFSET, start_instances),
+            (END_MARKER_VERTEX_OFFSET, end_instances),
+        ] {
+            if !instances.is_empty() {
+                draw(pass, offset..offset + marker_vertices, instances);
+            }
+        }
+    }
+}
+
+/// Draws `instances` of the `vertices` of a polyline, for each of its `copies`. The copies of a
+/// segment are consecutive instances, which the shader tells apart by their index.
+///
+/// WebGL2 can't start drawing from an instance other than the first, so with the `webgl` feature
+/// the vertex buffer is rebound at the first instance instead, and copies are drawn separately.
+#[inline]
+#[cfg_attr(not(feature = "webgl"), allow(unused_variables))]
+fn draw_instances<'w>(
+    pass: &mut TrackedRenderPass<'w>,
+    stats: &RenderPolylineStats,
+    gpu_polyline: &'w GpuPolyline,
+    vertices: std::ops::Range<u32>,
+    instances: std::ops::Range<u32>,
+    copies: u32,
+) {
+    stats.add_draw_call();
+    #[cfg(not(feature = "webgl"))]
+    pass.draw(vertices, instances.start * copies..instances.end * copies);
+    #[cfg(feature = "webgl")]
+    {
+        debug_assert_eq!(copies, 1);
+        // Matches the array stride in `PolylinePipeline::specialize()`
+        let instance_stride = match gpu_polyline.mode {
+            PolylineMode::LineStrip => VERTEX_SIZE,
+            PolylineMode::LineList => 2 * VERTEX_SIZE,
+        };
+        pass.set_vertex_buffer(
+            0,
+            gpu_polyline
+                .vertex_buffer
+                .slice(instances.start as u64 * instance_stride..),
+        );
+        pass.draw(vertices, 0..instances.len() as u32);
+    }
+}
@@ -15,6 +15,14 @@ var<uniform> view: View;
 
 struct Polyline {
     model: mat4x4<f32>;
+    // Replaces the material width unless negative
+    width: f32;
+    opacity: f32;
+    // Fraction of the length of the line drawn, the whole line from `1.0` on
+    reveal: f32;
+    // Total length of the line, which arc lengths are divided by for gradients, tapering and
+    // reveals
+    total_length: f32;
 };
 
 [[group(1), binding(0)]]
@@ -23,26 +31,377 @@ var<uniform> polyline: Polyline;
 
 struct PolylineMaterial {
     color: vec4<f32>;
+    color_end: vec4<f32>;
+    clip_plane: vec4<f32>;
+    outline_color: vec4<f32>;
+    // Replaces the color of the line for markers unless its alpha is negative
+    marker_color: vec4<f32>;
     width: f32;
+    min_width: f32;
+    max_width: f32;
+    cap: u32;
+    join: u32;
+    round_resolution: u32;
+    miter_limit: f32;
+    dash_length: f32;
+    gap_length: f32;
+    dash_offset: f32;
+    stipple_pattern: u32;
+    stipple_factor: f32;
+    stipple_pixels: u32;
+    depth_bias: f32;
+    texture_scale: f32;
+    arrowhead_size: f32;
+    marker_size: f32;
+    fade_start: f32;
+    fade_end: f32;
+    emissive_intensity: f32;
+    colormap_min: f32;
+    colormap_max: f32;
+    colormap: u32;
+    outline_width: f32;
+    taper: u32;
+    debug_wireframe: u32;
+    ribbon_normal: vec3<f32>;
 };
 
 [[group(2), binding(0)]]
 var<uniform> material: PolylineMaterial;
+[[group(2), binding(1)]]
+var material_texture: texture_2d<f32>;
+[[group(2), binding(2)]]
+var material_sampler: sampler;
+[[group(2), binding(3)]]
+var colormap_texture: texture_2d<f32>;
+[[group(2), binding(4)]]
+var colormap_sampler: sampler;
 
+// The two endpoints of a segment and their attributes, along with the position of the vertex
+// after the segment, which the join at its end is drawn towards, and of the vertex before it.
+// The first vertex of an open line, or of a segment of a line list, is its own previous vertex.
 struct Vertex {
     [[location(0)]] I_Point0_: vec3<f32>;
     [[location(1)]] I_Point1_: vec3<f32>;
+    [[location(2)]] I_Color0_: vec4<f32>;
+    [[location(3)]] I_Color1_: vec4<f32>;
+    [[location(4)]] I_Point2_: vec3<f32>;
+    [[location(11)]] I_PointPrev_: vec3<f32>;
+    [[location(5)]] I_ArcLength0_: f32;
+    [[location(6)]] I_ArcLength1_: f32;
+    [[location(7)]] I_Width0_: f32;
+    [[location(8)]] I_Width1_: f32;
+    [[location(9)]] I_Scalar0_: f32;
+    [[location(10)]] I_Scalar1_: f32;
     [[builtin(vertex_index)]] index: u32;
+    [[builtin(instance_index)]] instance: u32;
+};
+
+#ifdef POLYLINE_STORAGE_VERTICES
+// Indexed line lists and instanced polylines read their vertices from storage buffers rather than
+// vertex attributes, see `GpuPolylineIndices` and `ExtractedPolylineInstances` in
+// `render/pipeline.rs`. Vertices have the layout of the vertex buffer.
+struct StorageVertices {
+    data: array<f32>;
+};
+
+[[group(3), binding(0)]]
+var<storage> storage_vertices: StorageVertices;
+
+// Must match `VERTEX_FLOATS` in `render/pipeline.rs`
+let VERTEX_FLOATS: u32 = 13u;
+
+struct StorageVertexInput {
+    [[builtin(vertex_index)]] index: u32;
+    [[builtin(instance_index)]] instance: u32;
+};
+
+// Reads the `vec3` at `offset` floats into `vertex`
+fn stored_vec3(vertex: u32, offset: u32) -> vec3<f32> {
+    let i = vertex * VERTEX_FLOATS + offset;
+    return vec3<f32>(
+        storage_vertices.data[i],
+        storage_vertices.data[i + 1u],
+        storage_vertices.data[i + 2u]
+    );
+}
+
+fn stored_color(vertex: u32) -> vec4<f32> {
+    let i = vertex * VERTEX_FLOATS + 6u;
+    return vec4<f32>(
+        storage_vertices.data[i],
+        storage_vertices.data[i + 1u],
+        storage_vertices.data[i + 2u],
+        storage_vertices.data[i + 3u]
+    );
+}
+
+fn stored_f32(vertex: u32, offset: u32) -> f32 {
+    return storage_vertices.data[vertex * VERTEX_FLOATS + offset];
+}
+
+#ifdef POLYLINE_INSTANCED
+struct PolylineInstances {
+    data: array<mat4x4<f32>>;
+};
+
+[[group(3), binding(2)]]
+var<storage> polyline_instances: PolylineInstances;
+#endif
+
+// Index of the segment drawn by `instance`. Instanced polylines draw every copy of a segment
+// as consecutive instances.
+fn instance_segment(instance: u32) -> u32 {
+    var segment = instance;
+#ifdef POLYLINE_INSTANCED
+    segment = instance / arrayLength(&polyline_instances.data);
+#endif
+    return segment;
+}
+
+#ifdef POLYLINE_INDEXED
+// Arc lengths depend on the segment, so they are stored along with its two vertex indices
+struct IndexedSegment {
+    vertices: vec2<u32>;
+    arc_lengths: vec2<f32>;
+};
+
+struct IndexedSegments {
+    data: array<IndexedSegment>;
 };
 
+[[group(3), binding(1)]]
+var<storage> indexed_segments: IndexedSegments;
+
+// Reads the segment drawn by `instance` into the attributes other polylines are drawn from
+fn storage_vertex(input: StorageVertexInput) -> Vertex {
+    let segment_index = instance_segment(input.instance);
+    let segment = indexed_segments.data[segment_index];
+    let v0 = segment.vertices.x;
+    let v1 = segment.vertices.y;
+    return Vertex(
+        stored_vec3(v0, 0u),
+        stored_vec3(v1, 0u),
+        stored_color(v0),
+        stored_color(v1),
+        // Segments of line lists have no joins, nor previous vertices
+        stored_vec3(v1, 0u),
+        stored_vec3(v0, 0u),
+        segment.arc_lengths.x,
+        segment.arc_lengths.y,
+        stored_f32(v0, 11u),
+        stored_f32(v1, 11u),
+        stored_f32(v0, 12u),
+        stored_f32(v1, 12u),
+        input.index,
+        segment_index
+    );
+}
+#endif
+
+#ifndef POLYLINE_INDEXED
+// Reads the segment drawn by `instance` into the attributes the vertex buffer would provide
+fn storage_vertex(input: StorageVertexInput) -> Vertex {
+    let segment = instance_segment(input.instance);
+    // Segments of line lists skip ahead two vertices, like the stride of their vertex buffer
+    var v0 = segment;
+#ifdef POLYLINE_LINE_LIST
+    v0 = 2u * segment;
+#endif
+    let v1 = v0 + 1u;
+    return Vertex(
+        stored_vec3(v0, 0u),
+        stored_vec3(v1, 0u),
+        stored_color(v0),
+        stored_color(v1),
+        stored_vec3(v1 + 1u, 0u),
+        stored_vec3(v0, 3u),
+        stored_f32(v0, 10u),
+        stored_f32(v1, 10u),
+        stored_f32(v0, 11u),
+        stored_f32(v1, 11u),
+        stored_f32(v0, 12u),
+        stored_f32(v1, 12u),
+        input.index,
+        segment
+    );
+}
+#endif
+#endif
+
 struct VertexOutput {
     [[builtin(position)]] clip_position: vec4<f32>;
     [[location(0)]] color: vec4<f32>;
+    [[location(1)]] arc_length: f32;
+    // Signed distance from the center of the line and half of the line width, in pixels
+    [[location(2)]] edge: vec2<f32>;
+    [[location(3)]] world_position: vec3<f32>;
+    [[location(4)]] scalar: f32;
+    // Position within the triangle being drawn and color of the segment, for `debug_wireframe`
+    [[location(5)]] barycentric: vec3<f32>;
+    [[location(6)]] debug_color: vec3<f32>;
+    // Fraction of the length of the line between the vertex and the end of the revealed part,
+    // positive where the line is drawn
+    [[location(7)]] reveal: f32;
 };
 
+// Vertex index ranges selecting which part of the line is drawn, these must match the constants
+// in `render/pipeline.rs`
+let START_CAP_VERTEX_OFFSET: u32 = 1024u;
+let END_CAP_VERTEX_OFFSET: u32 = 2048u;
+let JOIN_VERTEX_OFFSET: u32 = 3072u;
+let START_ARROWHEAD_VERTEX_OFFSET: u32 = 4096u;
+let END_ARROWHEAD_VERTEX_OFFSET: u32 = 5120u;
+let START_MARKER_VERTEX_OFFSET: u32 = 6144u;
+let END_MARKER_VERTEX_OFFSET: u32 = 7168u;
+
+// Normalizes `v`, or returns `fallback` if `v` is too short to have a direction, like the
+// direction of a segment between coincident points, which `normalize` turns into NaNs
+fn safe_normalize2(v: vec2<f32>, fallback: vec2<f32>) -> vec2<f32> {
+    let length_squared = dot(v, v);
+    if (length_squared > 1e-12) {
+        return v * inverseSqrt(length_squared);
+    }
+    return fallback;
+}
+
+fn safe_normalize3(v: vec3<f32>, fallback: vec3<f32>) -> vec3<f32> {
+    let length_squared = dot(v, v);
+    if (length_squared > 1e-12) {
+        return v * inverseSqrt(length_squared);
+    }
+    return fallback;
+}
+
+// Unit vector perpendicular to the non-zero `v`, crossed with the axis it is the least aligned
+// with so that the result never degenerates
+fn orthogonal(v: vec3<f32>) -> vec3<f32> {
+    let a = abs(v);
+    var axis = vec3<f32>(0.0, 0.0, 1.0);
+    if (a.x <= a.y && a.x <= a.z) {
+        axis = vec3<f32>(1.0, 0.0, 0.0);
+    } else if (a.y <= a.z) {
+        axis = vec3<f32>(0.0, 1.0, 0.0);
+    }
+    return normalize(cross(v, axis));
+}
+
+// Must match `LineCap`, `LineJoin` and `TaperMode` in `material.rs`
+let CAP_SQUARE: u32 = 1u;
+let JOIN_MITER: u32 = 1u;
+let JOIN_BEVEL: u32 = 2u;
+let TAPER_START: u32 = 1u;
+let TAPER_END: u32 = 2u;
+let TAPER_BOTH: u32 = 3u;
+
+let PI: f32 = 3.141592653589793;
+
+// Offset of a cap vertex relative to the line endpoint, in units of line width.
+// `direction` points away from the line.
+fn cap_offset(index: u32, direction: vec2<f32>, normal: vec2<f32>) -> vec2<f32> {
+    if (material.cap == CAP_SQUARE) {
+        var corners: array<vec2<f32>, 6u> = array<vec2<f32>, 6u>(
+            vec2<f32>(0.0, -0.5),
+            vec2<f32>(0.5, -0.5),
+            vec2<f32>(0.5, 0.5),
+            vec2<f32>(0.0, -0.5),
+            vec2<f32>(0.5, 0.5),
+            vec2<f32>(0.0, 0.5)
+        );
+        let corner = corners[index];
+        return corner.x * direction + corner.y * normal;
+    }
+
+    // Round caps are a fan of triangles around the endpoint
+    let corner = index % 3u;
+    if (corner == 0u) {
+        return vec2<f32>(0.0, 0.0);
+    }
+    let step = f32(index / 3u + corner - 1u);
+    let angle = PI * (step / f32(material.round_resolution) - 0.5);
+    return 0.5 * (cos(angle) * direction + sin(angle) * normal);
+}
+
+// Offset of an arrowhead vertex relative to the line endpoint, in units of line
+// width. The arrowhead's base is centered on the endpoint and its tip points along `direction`.
+fn arrowhead_offset(index: u32, direction: vec2<f32>, normal: vec2<f32>) -> vec2<f32> {
+    var corners: array<vec2<f32>, 3u> = array<vec2<f32>, 3u>(
+        vec2<f32>(0.0, -0.5),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 0.5)
+    );
+    let corner = material.arrowhead_size * corners[index];
+    return corner.x * direction + corner.y * normal;
+}
+
+// Offset of a marker vertex relative to the vertex it marks, in units of line width. Markers are
+// a fan of triangles around the vertex, twice as many as a round cap.
+fn marker_offset(index: u32) -> vec2<f32> {
+    let corner = index % 3u;
+    if (corner == 0u) {
+        return vec2<f32>(0.0, 0.0);
+    }
+    let step = f32(index / 3u + corner - 1u);
+    let angle = PI * step / f32(material.round_resolution);
+    return 0.5 * material.marker_size * vec2<f32>(cos(angle), sin(angle));
+}
+
+// Offset of a join vertex relative to the joint, in units of line width. `normal0`
+// and `normal1` are the normals of the incoming and outgoing segments.
+fn join_offset(index: u32, normal0: vec2<f32>, normal1: vec2<f32>, direction0: vec2<f32>) -> vec2<f32> {
+    // The gap to fill is on the outside of the turn
+    let turn = direction0.x * normal1.x + direction0.y * normal1.y;
+    let side = select(-0.5, 0.5, turn > 0.0);
+    let corner0 = side * normal0;
+    let corner1 = side * normal1;
+
+    let corner = index % 3u;
+    if (corner == 0u) {
+        return vec2<f32>(0.0, 0.0);
+    }
+
+    if (material.join == JOIN_BEVEL) {
+        return select(corner1, corner0, corner == 1u);
+    }
+
+    if (material.join == JOIN_MITER) {
+        // Two triangles, each spanning from one of the segment corners to the miter tip
+        // Segments doubling back on themselves have opposite corners and no miter
+        let miter = safe_normalize2(corner0 + corner1, corner0);
+        let miter_length = 0.5 / max(dot(miter, corner0) * 2.0, 0.0001);
+        var tip = miter * miter_length;
+        if (miter_length > 0.5 * material.miter_limit) {
+            tip = 0.5 * (corner0 + corner1);
+        }
+        if (index < 3u) {
+            return select(tip, corner0, corner == 1u);
+        }
+        return select(corner1, tip, corner == 1u);
+    }
+
+    // Round joins are a fan of triangles sweeping from one corner to the other
+    let angle0 = atan2(corner0.y, corner0.x);
+    var delta = atan2(corner1.y, corner1.x) - angle0;
+    if (delta > PI) {
+        delta = delta - 2.0 * PI;
+    }
+    if (delta < -PI) {
+        delta = delta + 2.0 * PI;
+    }
+    let step = f32(index / 3u + corner - 1u);
+    let angle = angle0 + delta * step / f32(material.round_resolution);
+    return 0.5 * vec2<f32>(cos(angle), sin(angle));
+}
+
 [[stage(vertex)]]
 // fn vertex([[builtin(vertex_index)]] vertex_index: u32, vertex: Vertex) -> VertexOutput {
+#ifdef POLYLINE_STORAGE_VERTICES
+fn vertex(input: StorageVertexInput) -> VertexOutput {
+    let vertex = storage_vertex(input);
+#endif
+#ifndef POLYLINE_STORAGE_VERTICES
 fn vertex(vertex: Vertex) -> VertexOutput {
+#endif
+    // Must match `SEGMENT_QUAD_VERTICES` in `polyline.rs`
     var positions: array<vec3<f32>, 6u> = array<vec3<f32>, 6u>(
         vec3<f32>(0.0, -0.5, 0.0),
         vec3<f32>(0.0, -0.5, 1.0),
@@ -51,25 +410,163 @@ fn vertex(vertex: Vertex) -> VertexOutput {
         vec3<f32>(0.0, 0.5, 1.0),
         vec3<f32>(0.0, 0.5, 0.0)
     );
-    let position = positions[vertex.index];
+
+    var model = polyline.model;
+#ifdef POLYLINE_INSTANCED
+    // Each copy of a segment is drawn by its own instance
+    let copy = input.instance % arrayLength(&polyline_instances.data);
+    model = model * polyline_instances.data[copy];
+#endif
 
     // algorithm based on https://wwwtyro.net/2019/11/18/instanced-lines.html
-    let clip0 = view.view_proj * polyline.model * vec4<f32>(vertex.I_Point0_, 1.0);
-    let clip1 = view.view_proj * polyline.model * vec4<f32>(vertex.I_Point1_, 1.0);
-    let clip = mix(clip0, clip1, position.z);
+    // The model matrix only transforms the endpoints, the line is expanded afterwards so its
+    // width doesn't depend on the scale of the entity
+    let clip0 = view.view_proj * model * vec4<f32>(vertex.I_Point0_, 1.0);
+    let clip1 = view.view_proj * model * vec4<f32>(vertex.I_Point1_, 1.0);
 
+    // The view size is kept up to date by Bevy, including after the window is resized
     let resolution = vec2<f32>(view.width, view.height);
     let screen0 = resolution * (0.5 * clip0.xy / clip0.w + 0.5);
     let screen1 = resolution * (0.5 * clip1.xy / clip1.w + 0.5);
 
-    let xBasis = normalize(screen1 - screen0);
-    let yBasis = vec2<f32>(-xBasis.y, xBasis.x);
+    // Basis of the plane the line is expanded in, with the segment along `xBasis`: the screen for
+    // billboarded lines, or the plane perpendicular to the normal of a ribbon, spanned by
+    // `ribbon_x` and `ribbon_y` in world space
+    var xBasis: vec2<f32>;
+    var yBasis: vec2<f32>;
+    #ifdef POLYLINE_RIBBON
+        let world0 = model * vec4<f32>(vertex.I_Point0_, 1.0);
+        let world1 = model * vec4<f32>(vertex.I_Point1_, 1.0);
+        // Zero length segments fall back to any direction in the plane, their quads have no
+        // area either way. Segments along the normal, or close enough that the cross product
+        // loses its precision, are widened along any direction perpendicular to them instead.
+        let normal = safe_normalize3(material.ribbon_normal, vec3<f32>(0.0, 1.0, 0.0));
+        let ribbon_x = safe_normalize3(world1.xyz - world0.xyz, orthogonal(normal));
+        let side = cross(normal, ribbon_x);
+        var ribbon_y = orthogonal(ribbon_x);
+        if (dot(side, side) > 1e-6) {
+            ribbon_y = normalize(side);
+        }
+        xBasis = vec2<f32>(1.0, 0.0);
+        yBasis = vec2<f32>(0.0, 1.0);
+    #endif
+    #ifndef POLYLINE_RIBBON
+        // Segments between coincident points, or pointing straight at the camera, have no
+        // direction on screen, their quads have no area but their caps are still drawn
+        xBasis = safe_normalize2(screen1 - screen0, vec2<f32>(1.0, 0.0));
+        yBasis = vec2<f32>(-xBasis.y, xBasis.x);
+    #endif
 
-    var line_width = material.width;
-    var color = material.color;
+    // `t` interpolates between the two endpoints, `offset` is the offset from the center of the
+    // line in units of line width
+    var t: f32;
+    var offset: vec2<f32>;
+    if (vertex.index >= END_MARKER_VERTEX_OFFSET) {
+        t = 1.0;
+        offset = marker_offset(vertex.index - END_MARKER_VERTEX_OFFSET);
+    } else if (vertex.index >= START_MARKER_VERTEX_OFFSET) {
+        t = 0.0;
+        offset = marker_offset(vertex.index - START_MARKER_VERTEX_OFFSET);
+    } else if (vertex.index >= END_ARROWHEAD_VERTEX_OFFSET) {
+        t = 1.0;
+        offset = arrowhead_offset(vertex.index - END_ARROWHEAD_VERTEX_OFFSET, xBasis, yBasis);
+    } else if (vertex.index >= START_ARROWHEAD_VERTEX_OFFSET) {
+        t = 0.0;
+        offset = arrowhead_offset(vertex.index - START_ARROWHEAD_VERTEX_OFFSET, -xBasis, -yBasis);
+    } else if (vertex.index >= JOIN_VERTEX_OFFSET) {
+        var xBasis2: vec2<f32>;
+        #ifdef POLYLINE_RIBBON
+            let world2 = model * vec4<f32>(vertex.I_Point2_, 1.0);
+            let direction2 = world2.xyz - world1.xyz;
+            xBasis2 = safe_normalize2(
+                vec2<f32>(dot(direction2, ribbon_x), dot(direction2, ribbon_y)),
+                xBasis
+            );
+        #endif
+        #ifndef POLYLINE_RIBBON
+            let clip2 = view.view_proj * model * vec4<f32>(vertex.I_Point2_, 1.0);
+            let screen2 = resolution * (0.5 * clip2.xy / clip2.w + 0.5);
+            xBasis2 = safe_normalize2(screen2 - screen1, xBasis);
+        #endif
+        let yBasis2 = vec2<f32>(-xBasis2.y, xBasis2.x);
+        t = 1.0;
+        offset = join_offset(vertex.index - JOIN_VERTEX_OFFSET, yBasis, yBasis2, xBasis);
+    } else if (vertex.index >= END_CAP_VERTEX_OFFSET) {
+        t = 1.0;
+        offset = cap_offset(vertex.index - END_CAP_VERTEX_OFFSET, xBasis, yBasis);
+    } else if (vertex.index >= START_CAP_VERTEX_OFFSET) {
+        t = 0.0;
+        offset = cap_offset(vertex.index - START_CAP_VERTEX_OFFSET, -xBasis, -yBasis);
+    } else {
+        let position = positions[vertex.index];
+        t = position.z;
+        offset = position.x * xBasis + position.y * yBasis;
+    }
+
+    // Distance from the center of the line in units of line width, interpolating to 0.5 along
+    // every outer edge. Segments and square caps measure it across the line, round caps and
+    // joins radially from the vertex they are centered on. Markers are measured radially too,
+    // against their own radius in `edge_scale` line widths. Arrowheads are much wider than the
+    // line and aren't faded.
+    var edge: f32;
+    var edge_scale = 1.0;
+    if (vertex.index >= START_MARKER_VERTEX_OFFSET) {
+        edge = length(offset);
+        edge_scale = material.marker_size;
+    } else if (vertex.index >= START_ARROWHEAD_VERTEX_OFFSET) {
+        edge = 0.0;
+    } else if (vertex.index < START_CAP_VERTEX_OFFSET ||
+        (vertex.index < JOIN_VERTEX_OFFSET && material.cap == CAP_SQUARE)) {
+        edge = dot(offset, yBasis);
+    } else {
+        edge = min(length(offset), 0.5);
+    }
+    let clip = mix(clip0, clip1, t);
+
+    let base_width = select(material.width, polyline.width, polyline.width >= 0.0);
+    var line_width = base_width * mix(vertex.I_Width0_, vertex.I_Width1_, t);
+    // The material color is a gradient along the line, with both ends being equal by default
+    let arc_length = mix(vertex.I_ArcLength0_, vertex.I_ArcLength1_, t);
+    let arc_fraction =
+        select(0.0, arc_length / polyline.total_length, polyline.total_length > 0.0);
+    let material_color = mix(material.color, material.color_end, arc_fraction);
+    var color = material_color * mix(vertex.I_Color0_, vertex.I_Color1_, t);
+    if (vertex.index >= START_MARKER_VERTEX_OFFSET && material.marker_color.a >= 0.0) {
+        color = material.marker_color;
+    }
+    color.a = color.a * polyline.opacity;
+
+    if (material.taper == TAPER_START) {
+        line_width = line_width * arc_fraction;
+    } else if (material.taper == TAPER_END) {
+        line_width = line_width * (1.0 - arc_fraction);
+    } else if (material.taper == TAPER_BOTH) {
+        line_width = line_width * (1.0 - abs(2.0 * arc_fraction - 1.0));
+    }
+
+    // Distance fade, using the depth along the view direction
+    if (material.fade_end > material.fade_start) {
+        let position = mix(vertex.I_Point0_, vertex.I_Point1_, t);
+        let view_position = view.inverse_view * model * vec4<f32>(position, 1.0);
+        let fade = (-view_position.z - material.fade_start) / (material.fade_end - material.fade_start);
+        color.a = color.a * (1.0 - clamp(fade, 0.0, 1.0));
+    }
+
+    #ifdef POLYLINE_WORLD_WIDTH
+        // Widths in world units, including those of ribbons, are converted to pixels at the
+        // depth of the line so that clamping and antialiasing work the same as for other lines
+        let pixels_per_unit = 0.5 * resolution.y * view.projection[1][1] / clip.w;
+        line_width = line_width * pixels_per_unit;
+    #endif
 
     #ifdef POLYLINE_PERSPECTIVE
          line_width = line_width / clip.w;
+    #endif
+
+    // Clamps the width in pixels, after it has been scaled with distance
+    line_width = clamp(line_width, material.min_width, material.max_width);
+
+    #ifdef POLYLINE_THIN_FADE
         // Line thinness fade from https://acegikmo.com/shapes/docs/#anti-aliasing
         if (line_width < 1.0) {
             color.a = color.a * line_width;
@@ -77,17 +574,80 @@ fn vertex(vertex: Vertex) -> VertexOutput {
         }
     #endif
 
-    let pt0 = screen0 + line_width * (position.x * xBasis + position.y * yBasis);
-    let pt1 = screen1 + line_width * (position.x * xBasis + position.y * yBasis);
-    let pt = mix(pt0, pt1, position.z);
+    // The outline is drawn by the fragment shader on the outermost pixels of the widened line
+    line_width = line_width + 2.0 * material.outline_width;
+
+    #ifdef POLYLINE_ANTIALIAS
+        // Widen the line by a pixel, the fragment shader fades out its outermost pixel so edges
+        // end up half covered at the requested width
+        line_width = line_width + 1.0;
+    #endif
+
+    // Billboarded lines are expanded in screen space, their world position is the one of the
+    // center of the line, which is what the clip plane cuts
+    var position: vec4<f32>;
+    var world_position: vec4<f32>;
+    #ifdef POLYLINE_RIBBON
+        let world_offset = (line_width / pixels_per_unit) * (offset.x * ribbon_x + offset.y * ribbon_y);
+        world_position = mix(world0, world1, t) + vec4<f32>(world_offset, 0.0);
+        position = view.view_proj * world_position;
+    #endif
+    #ifndef POLYLINE_RIBBON
+        let pt = mix(screen0, screen1, t) + line_width * offset;
+        position = vec4<f32>(clip.w * ((2.0 * pt) / resolution - 1.0), clip.z, clip.w);
+        world_position = model * vec4<f32>(mix(vertex.I_Point0_, vertex.I_Point1_, t), 1.0);
+    #endif
+
+    // Bevy uses reverse z, the depth range extends from `position.w` at the near plane to 0.0
+    // at infinity
+    var depth = position.z;
+    if (material.depth_bias >= 0.0) {
+        depth = depth * (1.0 - material.depth_bias);
+    } else {
+        // Interpolates exponentially between `depth` and `position.w`, the epsilon keeps a bias
+        // of -1.0 just behind the near plane so the line isn't clipped. Lines at infinity have a
+        // depth of 0.0, which is clamped so that the ratio stays finite
+        let epsilon = 4.88e-04;
+        depth = depth * exp2(-material.depth_bias * log2(position.w / max(depth, 1e-4) - epsilon));
+    }
 
-    let depth = clip.z;
+    // Fully revealed lines stay clear of the cut, including their end caps
+    let reveal = select(1.0, polyline.reveal - arc_fraction, polyline.reveal < 1.0);
+    let scalar = mix(vertex.I_Scalar0_, vertex.I_Scalar1_, t);
 
-    return VertexOutput(vec4<f32>(clip.w * ((2.0 * pt) / resolution - 1.0), depth, clip.w), color);
+    // Every part of the line is a list of triangles, so consecutive indices are their corners
+    let corner = vertex.index % 3u;
+    let barycentric = vec3<f32>(f32(corner == 0u), f32(corner == 1u), f32(corner == 2u));
+    // Spreads the hues of consecutive segments with the golden ratio, so neighbours differ
+    let hue = fract(f32(vertex.instance) * 0.618034);
+    let debug_color = clamp(
+        abs(fract(hue + vec3<f32>(0.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0) - 1.0,
+        vec3<f32>(0.0),
+        vec3<f32>(1.0)
+    );
+
+    return VertexOutput(
+        vec4<f32>(position.xy, depth, position.w),
+        color,
+        arc_length,
+        vec2<f32>(edge * line_width, 0.5 * line_width * edge_scale),
+        world_position.xyz,
+        scalar,
+        barycentric,
+        debug_color,
+        reveal
+    );
 };
 
 struct FragmentInput {
     [[location(0)]] color: vec4<f32>;
+    [[location(1)]] arc_length: f32;
+    [[location(2)]] edge: vec2<f32>;
+    [[location(3)]] world_position: vec3<f32>;
+    [[location(4)]] scalar: f32;
+    [[location(5)]] barycentric: vec3<f32>;
+    [[location(6)]] debug_color: vec3<f32>;
+    [[location(7)]] reveal: f32;
 };
 
 struct FragmentOutput {
@@ -96,5 +656,111 @@ struct FragmentOutput {
 
 [[stage(fragment)]]
 fn fragment(in: FragmentInput) -> FragmentOutput {
-    return FragmentOutput(in.color);
+    // Arc length covered by a pixel, taken before any discard since derivatives need all
+    // neighbouring fragments
+    let arc_length_per_pixel = max(fwidth(in.arc_length), 1e-6);
+    let barycentric_per_pixel = fwidth(in.barycentric);
+    let reveal_per_pixel = max(fwidth(in.reveal), 1e-6);
+
+    if (material.debug_wireframe != 0u) {
+        // The raw triangles, tinted by segment and half transparent so that overlaps stand
+        // out, with their edges drawn in white
+        let edges = clamp(
+            in.barycentric / max(barycentric_per_pixel, vec3<f32>(1e-6)),
+            vec3<f32>(0.0),
+            vec3<f32>(1.0)
+        );
+        let interior = min(min(edges.x, edges.y), edges.z);
+        return FragmentOutput(vec4<f32>(mix(vec3<f32>(1.0), in.debug_color, interior), 0.5));
+    }
+
+    // The plane is all zeros without clipping, which never discards
+    if (dot(material.clip_plane, vec4<f32>(in.world_position, 1.0)) < 0.0) {
+        discard;
+    }
+
+    // Cuts the line at the end of its revealed part, which like the ends of dashes is faded
+    // over a pixel when antialiased
+    var reveal_coverage = 1.0;
+    #ifdef POLYLINE_ANTIALIAS
+        reveal_coverage = clamp(in.reveal / reveal_per_pixel + 0.5, 0.0, 1.0);
+        if (reveal_coverage <= 0.0) {
+            discard;
+        }
+    #endif
+    #ifndef POLYLINE_ANTIALIAS
+        if (in.reveal < 0.0) {
+            discard;
+        }
+    #endif
+
+    var dash_coverage = 1.0;
+    if (material.gap_length > 0.0 && material.dash_length > 0.0) {
+        let period = material.dash_length + material.gap_length;
+        // `%` keeps the sign of its left operand, wrap the part of the line before the offset
+        var phase = (in.arc_length - material.dash_offset) % period;
+        if (phase < 0.0) {
+            phase = phase + period;
+        }
+        #ifdef POLYLINE_ANTIALIAS
+            // Signed distance to the closest end of a dash, positive within dashes, which fades
+            // their ends over a pixel like the edges of the line
+            var dash_distance = min(phase, material.dash_length - phase);
+            if (phase > material.dash_length) {
+                dash_distance = -min(phase - material.dash_length, period - phase);
+            }
+            dash_coverage = clamp(dash_distance / arc_length_per_pixel + 0.5, 0.0, 1.0);
+            if (dash_coverage <= 0.0) {
+                discard;
+            }
+        #endif
+        #ifndef POLYLINE_ANTIALIAS
+            if (phase > material.dash_length) {
+                discard;
+            }
+        #endif
+    }
+
+    // Materials without a stipple have a factor of zero
+    if (material.stipple_factor > 0.0) {
+        let stipple_length = select(
+            in.arc_length,
+            in.arc_length / arc_length_per_pixel,
+            material.stipple_pixels != 0u
+        );
+        let bit = u32(max(floor(stipple_length / material.stipple_factor), 0.0)) % 16u;
+        if (((material.stipple_pattern >> bit) & 1u) == 0u) {
+            discard;
+        }
+    }
+
+    // Materials without a texture sample a white one
+    let uv = vec2<f32>(
+        in.arc_length * material.texture_scale,
+        0.5 + 0.5 * in.edge.x / in.edge.y
+    );
+    var color = in.color * textureSample(material_texture, material_sampler, uv);
+    if (material.colormap != 0u) {
+        let range = material.colormap_max - material.colormap_min;
+        let u = (in.scalar - material.colormap_min) / select(range, 1.0, range == 0.0);
+        color = color * textureSample(colormap_texture, colormap_sampler, vec2<f32>(u, 0.5));
+    }
+    color = vec4<f32>(color.rgb * material.emissive_intensity, color.a);
+
+    if (material.outline_width > 0.0) {
+        var inner = in.edge.y - material.outline_width;
+        #ifdef POLYLINE_ANTIALIAS
+            inner = inner - 0.5;
+        #endif
+        // Blends into the outline over a pixel, to soften the boundary between the two colors
+        let outline = clamp(abs(in.edge.x) - inner + 0.5, 0.0, 1.0);
+        color = mix(color, material.outline_color, outline);
+    }
+
+    #ifdef POLYLINE_ANTIALIAS
+        color.a = color.a * clamp(in.edge.y - abs(in.edge.x), 0.0, 1.0) * dash_coverage
+            * reveal_coverage;
+    #endif
+
+    return FragmentOutput(color);
 };
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+
+use crate::{
+    color::Color,
+    polyline::{Polyline, PolylineMode},
+};
+
+impl Polyline {
+    /// Creates a line list of a square grid in the XZ plane, centered on the origin, for a
+    /// ground grid in 3D scenes. The grid is `size` wide and split into `divisions` cells along
+    /// each axis, so it has `divisions + 1` lines in each direction.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let grid = Polyline::grid(10.0, 10);
+    /// assert_eq!(grid.vertices.len(), 2 * 11 * 2);
+    /// ```
+    pub fn grid(size: f32, divisions: u32) -> Self {
+        let divisions = divisions.max(1);
+        let half = 0.5 * size;
+        let step = size / divisions as f32;
+        let vertices = (0..=divisions)
+            .flat_map(|i| {
+                let offset = -half + i as f32 * step;
+                [
+                    Vec3::new(offset, 0.0, -half),
+                    Vec3::new(offset, 0.0, half),
+                    Vec3::new(-half, 0.0, offset),
+                    Vec3::new(half, 0.0, offset),
+                ]
+            })
+            .collect();
+        Polyline {
+            vertices,
+            mode: PolylineMode::LineList,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a line list of the X, Y and Z axes, each going from the origin to `length` along
+    /// its axis and colored with the matching entry of `colors`. Draw it with a white material to
+    /// show the colors as is.
+    ///
+    /// ```no_run
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// # #[cfg(feature = "render")]
+    /// fn spawn_axes(
+    ///     mut commands: Commands,
+    ///     mut polylines: ResMut<Assets<Polyline>>,
+    ///     mut materials: ResMut<Assets<PolylineMaterial>>,
+    /// ) {
+    ///     commands.spawn_bundle(PolylineBundle {
+    ///         polyline: polylines.add(Polyline::axes(1.0, [Color::RED, Color::GREEN, Color::BLUE])),
+    ///         material: materials.add(PolylineMaterial {
+    ///             width: 3.0,
+    ///             ..Default::default()
+    ///         }),
+    ///         ..Default::default()
+    ///     });
+    /// }
+    /// ```
+    pub fn axes(length: f32, colors: [Color; 3]) -> Self {
+        let axes = [Vec3::X, Vec3::Y, Vec3::Z];
+        Polyline {
+            vertices: axes
+                .iter()
+                .flat_map(|axis| [Vec3::ZERO, length * *axis])
+                .collect(),
+            colors: colors.iter().flat_map(|color| [*color, *color]).collect(),
+            mode: PolylineMode::LineList,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a closed line strip approximating the circle around `center` in the plane with
+    /// the normal `normal`, with `segments` segments. In the XY plane, with a normal of
+    /// `Vec3::Z`, the first vertex is on the X axis from the center and the circle runs
+    /// counterclockwise.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let circle = Polyline::circle(Vec3::ZERO, Vec3::Y, 2.0, 32);
+    /// assert_eq!(circle.vertices.len(), 32);
+    /// assert!(circle.closed);
+    /// assert!(circle
+    ///     .vertices
+    ///     .iter()
+    ///     .all(|v| v.y.abs() < 1e-5 && (v.length() - 2.0).abs() < 1e-5));
+    /// ```
+    pub fn circle(center: Vec3, normal: Vec3, radius: f32, segments: u32) -> Self {
+        Self::regular_polygon(center, normal, radius, segments)
+    }
+
+    /// Creates a closed regular polygon with `sides` sides and its corners on the circle of
+    /// `radius` around `center`, in the plane with the normal `normal`. Oriented like
+    /// [`Polyline::circle`], with a corner on the X axis of the plane.
+    pub fn regular_polygon(center: Vec3, normal: Vec3, radius: f32, sides: u32) -> Self {
+        let sides = sides.max(3);
+        let rotation = plane_rotation(normal);
+        let corners = (0..sides).map(|i| {
+            let angle = i as f32 / sides as f32 * std::f32::consts::TAU;
+            center + rotation * Vec3::new(radius * angle.cos(), radius * angle.sin(), 0.0)
+        });
+        Polyline {
+            closed: true,
+            ..Self::from_points(corners)
+        }
+    }
+
+    /// Creates a closed star with `points` points, alternating between corners at
+    /// `outer_radius` and `inner_radius` from `center`, in the plane with the normal `normal`.
+    /// Oriented like [`Polyline::circle`], with a point on the X axis of the plane.
+    pub fn star(
+        center: Vec3,
+        normal: Vec3,
+        outer_radius: f32,
+        inner_radius: f32,
+        points: u32,
+    ) -> Self {
+        let corners = 2 * points.max(2);
+        let rotation = plane_rotation(normal);
+        let corners = (0..corners).map(|i| {
+            let angle = i as f32 / corners as f32 * std::f32::consts::TAU;
+            let radius = if i % 2 == 0 {
+                outer_radius
+            } else {
+                inner_radius
+            };
+            center + rotation * Vec3::new(radius * angle.cos(), radius * angle.sin(), 0.0)
+        });
+        Polyline {
+            closed: true,
+            ..Self::from_points(corners)
+        }
+    }
+
+    /// Creates a closed rectangle of `size` around `center`, in the plane with the normal
+    /// `normal`. Oriented like [`Polyline::circle`]: in the XY plane, with a normal of `Vec3::Z`,
+    /// its sides are aligned with the X and Y axes and it runs counterclockwise from its lowest
+    /// corner, which suits 2D cameras and UI overlays.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_polyline::prelude::*;
+    /// let rectangle = Polyline::rectangle(Vec3::new(1.0, 0.5, 0.0), Vec3::Z, Vec2::new(2.0, 1.0));
+    /// assert_eq!(rectangle.vertices[0], Vec3::ZERO);
+    /// assert_eq!(rectangle.vertices[2], Vec3::new(2.0, 1.0, 0.0));
+    /// assert!(rectangle.closed);
+    ///
+    /// // A rectangle on the ground
+    /// let rectangle = Polyline::rectangle(Vec3::ZERO, Vec3::Y, Vec2::new(2.0, 1.0));
+    /// assert!(rectangle.vertices.iter().all(|v| v.y.abs() < 1e-5));
+    /// ```
+    pub fn rectangle(center: Vec3, normal: Vec3, size: Vec2) -> Self {
+        let rotation = plane_rotation(normal);
+        let half = size / 2.0;
+        let corners = [
+            Vec2::new(-half.x, -half.y),
+            Vec2::new(half.x, -half.y),
+            Vec2::new(half.x, half.y),
+            Vec2::new(-half.x, half.y),
+        ]
+        .map(|corner| center + rotation * corner.extend(0.0));
+        Polyline {
+            closed: true,
+            ..Self::from_points(corners)
+        }
+    }
+}
+
+/// Rotation from the XY plane into the plane with the normal `normal`, or no rotation for a zero
+/// normal.
+fn plane_rotation(normal: Vec3) -> Quat {
+    let normal = normal.normalize_or_zero();
+    if normal == Vec3::ZERO {
+        Quat::IDENTITY
+    } else {
+        Quat::from_rotation_arc(Vec3::Z, normal)
+    }
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+
+use crate::{color::Color, polyline::Polyline};
+
+/// Limits the [`Polyline`] of an entity to its `max_points` most recent vertices, for trails
+/// following a moving object. Push new positions to the end of the polyline, and the oldest ones
+/// are removed from its start at the end of the frame.
+///
+/// With `fade` enabled the polyline's per-vertex colors are replaced by a ramp from transparent
+/// at the oldest vertex to opaque at the newest. Fading only shows if the material blends, see
+/// [`BlendMode`](crate::material::BlendMode).
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolylineTrail {
+    pub max_points: usize,
+    pub fade: bool,
+}
+
+impl PolylineTrail {
+    pub fn new(max_points: usize) -> Self {
+        Self {
+            max_points,
+            fade: false,
+        }
+    }
+
+    pub fn with_fade(mut self, fade: bool) -> Self {
+        self.fade = fade;
+        self
+    }
+}
+
+/// Trims and fades the polylines of entities with a [`PolylineTrail`].
+///
+/// Polylines are only accessed mutably when they need to change, so untouched trails don't get
+/// their vertex buffer re-uploaded.
+pub fn update_polyline_trails(
+    mut polylines: ResMut<Assets<Polyline>>,
+    query: Query<(&PolylineTrail, &Handle<Polyline>)>,
+) {
+    for (trail, handle) in query.iter() {
+        let needs_update = match polylines.get(handle) {
+            Some(polyline) => {
+                polyline.vertices.len() > trail.max_points
+                    || (trail.fade && polyline.colors.len() != polyline.vertices.len())
+            }
+            None => false,
+        };
+        if !needs_update {
+            continue;
+        }
+        let polyline = polylines.get_mut(handle).unwrap();
+
+        // Draining shifts the remaining vertices in place, keeping the allocation around
+        polyline.truncate_front(trail.max_points);
+
+        if trail.fade {
+            let count = polyline.vertices.len();
+            let tail = count.max(2) - 1;
+            polyline.colors.clear();
+            polyline
+                .colors
+                .extend((0..count).map(|i| Color::rgba(1.0, 1.0, 1.0, i as f32 / tail as f32)));
+        }
+    }
+}